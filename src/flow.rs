@@ -0,0 +1,158 @@
+/// A steady stream of one item, passed along links between production nodes
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ItemFlow {
+    /// The internal name of the item in the recipe database
+    pub item: String,
+    pub per_minute: f64,
+}
+
+/// What a node needs through one of its inputs, used to work out the surplus of the outputs linked to it
+#[derive(Clone, Debug, PartialEq)]
+pub enum Demand {
+    /// Needs this much of an item or fluid
+    Flow(ItemFlow),
+    /// Takes whatever is left over, voiding or sinking it
+    Excess,
+}
+
+/// Splits what an output makes between the links from it, given the demand of the input of each link, in link order
+/// Inputs needing the item get as much of what they need as is left, and the rest is shared evenly by the inputs
+/// taking excess or without a demand. Without any of those, the rest stays at the output as surplus
+pub fn split_flow(flow: &ItemFlow, demands: &[Option<&Demand>]) -> Vec<f64> {
+    let mut left = flow.per_minute;
+    let mut shares: Vec<f64> = demands
+        .iter()
+        .map(|demand| match demand {
+            Some(Demand::Flow(needed)) if needed.item == flow.item => {
+                let share = needed.per_minute.min(left).max(0.0);
+                left -= share;
+                share
+            }
+            _ => 0.0,
+        })
+        .collect();
+    let open: Vec<_> = (0..demands.len()).filter(|i| matches!(demands[*i], None | Some(Demand::Excess))).collect();
+    for i in open.iter() {
+        shares[*i] = left / open.len() as f64;
+    }
+    shares
+}
+
+/// Formats an amount per minute with at most two decimals, dropping trailing zeros
+pub fn format_rate(per_minute: f64) -> String {
+    let text = format!("{per_minute:.2}");
+    text.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+/// Formats a power in watts with the largest fitting unit, like `4.5 MW`
+pub fn format_power(watts: f64) -> String {
+    let (value, unit) = [(1e9, "GW"), (1e6, "MW"), (1e3, "kW")]
+        .into_iter()
+        .find(|(scale, _)| watts.abs() >= *scale)
+        .map_or((watts, "W"), |(scale, unit)| (watts / scale, unit));
+    format!("{} {unit}", format_rate(value))
+}
+
+/// A steady stream of one fluid, passed along pipes between production nodes
+/// Kept apart from `ItemFlow`, so fluid ports can't be linked to item ports
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FluidFlow {
+    /// The internal name of the fluid in the recipe database
+    pub fluid: String,
+    pub per_minute: f64,
+}
+
+/// The length given to new pipes
+pub const DEFAULT_PIPE_LENGTH: u32 = 10;
+/// The longest pipe the throughput is known for, which links can't be longer than
+pub const MAX_PIPE_LENGTH: u32 = 1000;
+
+/// The most fluid pipes of a length carry, as (pipes, units per second), from the Factorio wiki
+const PIPE_THROUGHPUT: [(u32, f64); 18] = [
+    (1, 6000.0),
+    (2, 3000.0),
+    (3, 3000.0),
+    (7, 2000.0),
+    (12, 1500.0),
+    (17, 1200.0),
+    (20, 1125.0),
+    (30, 1050.0),
+    (50, 1000.0),
+    (100, 936.0),
+    (150, 800.0),
+    (200, 600.0),
+    (261, 500.0),
+    (300, 400.0),
+    (400, 300.0),
+    (600, 200.0),
+    (800, 150.0),
+    (1000, 125.0),
+];
+
+/// The most fluid a pipe `length` pipes long carries per minute
+/// Lengths between the ones in the table are interpolated, and pipes longer than `MAX_PIPE_LENGTH` carry as much as that
+pub fn pipe_throughput(length: u32) -> f64 {
+    let length = length.max(1);
+    let per_second = match PIPE_THROUGHPUT.iter().position(|(pipes, _)| *pipes >= length) {
+        Some(0) => PIPE_THROUGHPUT[0].1,
+        Some(index) => {
+            let ((shorter, shorter_flow), (longer, longer_flow)) = (PIPE_THROUGHPUT[index - 1], PIPE_THROUGHPUT[index]);
+            let t = (length - shorter) as f64 / (longer - shorter) as f64;
+            shorter_flow + (longer_flow - shorter_flow) * t
+        }
+        None => PIPE_THROUGHPUT[PIPE_THROUGHPUT.len() - 1].1,
+    };
+    per_second * 60.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ore(per_minute: f64) -> ItemFlow {
+        ItemFlow { item: "ore".to_owned(), per_minute }
+    }
+
+    #[test]
+    fn pipe_throughput_matches_the_table() {
+        for (pipes, per_second) in PIPE_THROUGHPUT {
+            assert_eq!(pipe_throughput(pipes), per_second * 60.0);
+        }
+        assert_eq!(PIPE_THROUGHPUT[PIPE_THROUGHPUT.len() - 1].0, MAX_PIPE_LENGTH);
+    }
+
+    #[test]
+    fn pipe_throughput_interpolates_between_lengths() {
+        // 5 pipes is halfway between 3 pipes at 3000/s and 7 pipes at 2000/s
+        assert_eq!(pipe_throughput(5), 2500.0 * 60.0);
+        assert_eq!(pipe_throughput(900), (150.0 + 125.0) / 2.0 * 60.0);
+        assert!(pipe_throughput(101) < pipe_throughput(100) && pipe_throughput(101) > pipe_throughput(150));
+    }
+
+    #[test]
+    fn pipe_throughput_keeps_to_the_table() {
+        assert_eq!(pipe_throughput(0), pipe_throughput(1));
+        assert_eq!(pipe_throughput(MAX_PIPE_LENGTH + 1), pipe_throughput(MAX_PIPE_LENGTH));
+    }
+
+    #[test]
+    fn split_flow_fills_demands_in_link_order() {
+        let (small, large) = (Demand::Flow(ore(30.0)), Demand::Flow(ore(60.0)));
+        assert_eq!(split_flow(&ore(100.0), &[Some(&small), Some(&large)]), vec![30.0, 60.0]);
+        assert_eq!(split_flow(&ore(50.0), &[Some(&small), Some(&large)]), vec![30.0, 20.0]);
+        assert_eq!(split_flow(&ore(50.0), &[Some(&large), Some(&small)]), vec![50.0, 0.0]);
+    }
+
+    #[test]
+    fn split_flow_shares_the_rest_evenly() {
+        let needed = Demand::Flow(ore(30.0));
+        assert_eq!(split_flow(&ore(90.0), &[Some(&Demand::Excess), Some(&needed), None]), vec![30.0, 30.0, 30.0]);
+        assert_eq!(split_flow(&ore(20.0), &[Some(&needed), Some(&Demand::Excess)]), vec![20.0, 0.0]);
+    }
+
+    #[test]
+    fn split_flow_gives_nothing_to_inputs_needing_something_else() {
+        let gears = Demand::Flow(ItemFlow { item: "gear".to_owned(), per_minute: 30.0 });
+        assert_eq!(split_flow(&ore(60.0), &[Some(&gears), None]), vec![0.0, 60.0]);
+    }
+}
@@ -0,0 +1,251 @@
+use serde_json::Value;
+
+use crate::recipe_database::ImportError;
+use crate::recipe_database::Item;
+use crate::recipe_database::ItemAmount;
+use crate::recipe_database::ItemKind;
+use crate::recipe_database::Machine;
+use crate::recipe_database::Recipe;
+use crate::recipe_database::RecipeDatabase;
+
+/// The prototype types that are items, as opposed to entities and other data
+const ITEM_TYPES: [&str; 20] = [
+    "item",
+    "ammo",
+    "armor",
+    "blueprint",
+    "blueprint-book",
+    "capsule",
+    "copy-paste-tool",
+    "deconstruction-item",
+    "gun",
+    "item-with-entity-data",
+    "item-with-inventory",
+    "item-with-label",
+    "item-with-tags",
+    "module",
+    "rail-planner",
+    "repair-tool",
+    "selection-tool",
+    "spidertron-remote",
+    "tool",
+    "upgrade-item",
+];
+
+/// The prototype types that craft recipes
+const CRAFTING_MACHINE_TYPES: [&str; 3] = ["assembling-machine", "furnace", "rocket-silo"];
+
+/// Reads the prototype data Factorio writes with `--dump-data`, found at `script-output/data-raw-dump.json`
+/// Both the 1.1 layout, where recipes can have `normal` and `expensive` variants, and the 2.0 layout are understood
+/// Only the normal variant of a recipe is imported
+pub fn import(text: &str) -> Result<RecipeDatabase, ImportError> {
+    let data: Value = serde_json::from_str(text)?;
+    let data = data
+        .as_object()
+        .ok_or_else(|| ImportError::Format("the top level is not an object of prototype types".to_owned()))?;
+    let mut database = RecipeDatabase::default();
+    for (prototype_type, prototypes) in data {
+        let Some(prototypes) = prototypes.as_object() else {
+            continue;
+        };
+        for (name, prototype) in prototypes {
+            if ITEM_TYPES.contains(&prototype_type.as_str()) {
+                let stack_size = prototype["stack_size"].as_u64().map(|stack_size| stack_size as u32);
+                database.items.insert(name.clone(), Item { name: name.clone(), display_name: name.clone(), kind: ItemKind::Item, stack_size });
+            } else if prototype_type == "fluid" {
+                database.items.insert(name.clone(), Item { name: name.clone(), display_name: name.clone(), kind: ItemKind::Fluid, stack_size: None });
+            } else if prototype_type == "recipe" {
+                database.recipes.insert(name.clone(), recipe(name, prototype)?);
+            } else if CRAFTING_MACHINE_TYPES.contains(&prototype_type.as_str()) {
+                database.machines.insert(name.clone(), machine(name, prototype)?);
+            }
+        }
+    }
+    Ok(database)
+}
+
+fn recipe(name: &str, prototype: &Value) -> Result<Recipe, ImportError> {
+    // 1.1 recipes with difficulty variants keep everything but the category in them
+    let data = if prototype["normal"].is_object() { &prototype["normal"] } else { prototype };
+    let ingredients = match data["ingredients"].as_array() {
+        Some(ingredients) => ingredients.iter().map(|ingredient| item_amount(name, ingredient)).collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+    let results = if let Some(results) = data["results"].as_array() {
+        results.iter().map(|result| item_amount(name, result)).collect::<Result<_, _>>()?
+    } else if let Some(result) = data["result"].as_str() {
+        vec![ItemAmount {
+            item: result.to_owned(),
+            amount: data["result_count"].as_f64().unwrap_or(1.0),
+        }]
+    } else {
+        Vec::new()
+    };
+    Ok(Recipe {
+        name: name.to_owned(),
+        display_name: name.to_owned(),
+        category: prototype["category"].as_str().unwrap_or("crafting").to_owned(),
+        time: data["energy_required"].as_f64().unwrap_or(0.5),
+        ingredients,
+        results,
+        alternate: false,
+    })
+}
+
+/// Reads an ingredient or result, written either as `["iron-plate", 2]` or as an object with a `name`
+/// Results can make a random amount between `amount_min` and `amount_max`, and only be made with some `probability`
+fn item_amount(recipe: &str, value: &Value) -> Result<ItemAmount, ImportError> {
+    let invalid = || ImportError::Format(format!("recipe {recipe} has an invalid ingredient or result: {value}"));
+    if let [item, amount] = value.as_array().map(Vec::as_slice).unwrap_or_default() {
+        return Ok(ItemAmount {
+            item: item.as_str().ok_or_else(invalid)?.to_owned(),
+            amount: amount.as_f64().ok_or_else(invalid)?,
+        });
+    }
+    let item = value["name"].as_str().ok_or_else(invalid)?.to_owned();
+    let amount = match value["amount"].as_f64() {
+        Some(amount) => amount,
+        None => (value["amount_min"].as_f64().ok_or_else(invalid)? + value["amount_max"].as_f64().ok_or_else(invalid)?) / 2.0,
+    };
+    let probability = value["probability"].as_f64().unwrap_or(1.0);
+    Ok(ItemAmount { item, amount: amount * probability })
+}
+
+fn machine(name: &str, prototype: &Value) -> Result<Machine, ImportError> {
+    let invalid = |field: &str| ImportError::Format(format!("machine {name} has no valid {field}"));
+    Ok(Machine {
+        name: name.to_owned(),
+        display_name: name.to_owned(),
+        crafting_speed: prototype["crafting_speed"].as_f64().ok_or_else(|| invalid("crafting_speed"))?,
+        categories: prototype["crafting_categories"]
+            .as_array()
+            .ok_or_else(|| invalid("crafting_categories"))?
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_owned)
+            .collect(),
+        power: prototype["energy_usage"]
+            .as_str()
+            .and_then(parse_energy)
+            .ok_or_else(|| invalid("energy_usage"))?,
+        power_exponent: 1.0,
+        // 1.1 keeps the slot count in a `module_specification`
+        module_slots: prototype["module_slots"]
+            .as_u64()
+            .or_else(|| prototype["module_specification"]["module_slots"].as_u64())
+            .unwrap_or(0) as u32,
+    })
+}
+
+/// Reads an amount of power or energy like `150kW` or `2.5MJ`, giving it in watts or joules
+fn parse_energy(text: &str) -> Option<f64> {
+    let (number, unit) = text.split_at(text.find(|c: char| c.is_ascii_alphabetic())?);
+    let multiplier = match unit.strip_suffix('W').or_else(|| unit.strip_suffix('J'))? {
+        "" => 1.0,
+        "k" => 1e3,
+        "M" => 1e6,
+        "G" => 1e9,
+        "T" => 1e12,
+        _ => return None,
+    };
+    Some(number.trim().parse::<f64>().ok()? * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/factorio-data-raw-dump.json");
+
+    fn amounts(amounts: &[ItemAmount]) -> Vec<(&str, f64)> {
+        amounts.iter().map(|amount| (amount.item.as_str(), amount.amount)).collect()
+    }
+
+    #[test]
+    fn imports_items_and_fluids() {
+        let database = import(FIXTURE).unwrap();
+        let plate = &database.items["iron-plate"];
+        assert_eq!(plate.kind, ItemKind::Item);
+        assert_eq!(plate.stack_size, Some(100));
+        assert_eq!(database.items["automation-science-pack"].stack_size, Some(200));
+        assert_eq!(database.items["water"].kind, ItemKind::Fluid);
+        assert_eq!(database.items["water"].stack_size, None);
+        assert!(!database.items.contains_key("transport-belt-entity"));
+    }
+
+    #[test]
+    fn reads_the_normal_variant_of_recipes() {
+        let database = import(FIXTURE).unwrap();
+        let gear = &database.recipes["iron-gear-wheel"];
+        assert_eq!(gear.category, "crafting");
+        assert_eq!(gear.time, 0.5);
+        assert_eq!(amounts(&gear.ingredients), [("iron-plate", 2.0)]);
+        assert_eq!(amounts(&gear.results), [("iron-gear-wheel", 1.0)]);
+    }
+
+    #[test]
+    fn reads_single_results_with_counts() {
+        let database = import(FIXTURE).unwrap();
+        let cable = &database.recipes["copper-cable"];
+        assert_eq!(amounts(&cable.results), [("copper-cable", 2.0)]);
+    }
+
+    #[test]
+    fn reads_recipes_in_the_2_0_layout() {
+        let database = import(FIXTURE).unwrap();
+        let science = &database.recipes["automation-science-pack"];
+        assert_eq!(science.time, 5.0);
+        assert_eq!(amounts(&science.ingredients), [("copper-plate", 1.0), ("iron-gear-wheel", 1.0)]);
+        let oil = &database.recipes["basic-oil-processing"];
+        assert_eq!(oil.category, "oil-processing");
+        assert_eq!(amounts(&oil.ingredients), [("crude-oil", 100.0)]);
+        assert_eq!(amounts(&oil.results), [("petroleum-gas", 45.0)]);
+    }
+
+    #[test]
+    fn averages_random_results() {
+        let database = import(FIXTURE).unwrap();
+        let uranium = &database.recipes["uranium-processing"];
+        let results = amounts(&uranium.results);
+        assert_eq!(results[0].0, "uranium-235");
+        assert!((results[0].1 - 0.007).abs() < 1e-9);
+        assert_eq!(results[1].0, "uranium-238");
+        assert!((results[1].1 - 0.993).abs() < 1e-9);
+        let scrap = &database.recipes["scrap-recycling"];
+        assert_eq!(amounts(&scrap.results), [("iron-gear-wheel", 0.4)]);
+    }
+
+    #[test]
+    fn imports_crafting_machines() {
+        let database = import(FIXTURE).unwrap();
+        let assembler = &database.machines["assembling-machine-2"];
+        assert_eq!(assembler.crafting_speed, 0.75);
+        assert_eq!(assembler.categories, ["crafting", "advanced-crafting"]);
+        assert_eq!(assembler.power, 150_000.0);
+        assert_eq!(assembler.module_slots, 2);
+        let furnace = &database.machines["stone-furnace"];
+        assert_eq!(furnace.power, 90_000.0);
+        assert_eq!(furnace.module_slots, 0);
+        let refinery = &database.machines["oil-refinery"];
+        assert_eq!(refinery.power, 420_000.0);
+        assert_eq!(refinery.module_slots, 3);
+        assert_eq!(database.machines.len(), 3);
+    }
+
+    #[test]
+    fn parses_energy_units() {
+        assert_eq!(parse_energy("90kW"), Some(90_000.0));
+        assert_eq!(parse_energy("2.5MJ"), Some(2_500_000.0));
+        assert_eq!(parse_energy("12W"), Some(12.0));
+        assert_eq!(parse_energy("12"), None);
+        assert_eq!(parse_energy("5kV"), None);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(matches!(import("[1, 2]"), Err(ImportError::Format(_))));
+        assert!(matches!(import("{"), Err(ImportError::Json(_))));
+        let bad_recipe = r#"{"recipe": {"broken": {"ingredients": [{"amount": 1}]}}}"#;
+        assert!(matches!(import(bad_recipe), Err(ImportError::Format(_))));
+    }
+}
@@ -0,0 +1,2 @@
+pub mod factorio;
+pub mod satisfactory;
@@ -0,0 +1,286 @@
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::recipe_database::ImportError;
+use crate::recipe_database::Item;
+use crate::recipe_database::ItemAmount;
+use crate::recipe_database::ItemKind;
+use crate::recipe_database::Machine;
+use crate::recipe_database::Recipe;
+use crate::recipe_database::RecipeDatabase;
+
+/// The native classes of buildings that craft recipes
+const MANUFACTURER_CLASSES: [&str; 2] = ["FGBuildableManufacturer", "FGBuildableManufacturerVariablePower"];
+
+/// Reads the `Docs.json` the game ships in `CommunityResources/Docs`, which is UTF-16 encoded
+/// UTF-8 files, such as ones that have been edited and saved again, are read as well
+/// Recipes only made by hand are left out, and the ones made with the build gun go into the construction recipes
+/// Each building gets a category of its own, named after the building, as the game has no recipe categories
+pub fn import(bytes: &[u8]) -> Result<RecipeDatabase, ImportError> {
+    let data: Value = serde_json::from_str(&decode(bytes)?)?;
+    let groups = data
+        .as_array()
+        .ok_or_else(|| ImportError::Format("the top level is not a list of native classes".to_owned()))?;
+
+    let mut database = RecipeDatabase::default();
+    // Items are told apart from other classes by having a form, as they are split over many native classes
+    for class in classes(groups, |_| true).filter(|class| class.contains_key("mForm")) {
+        let name = string(class, "ClassName")?;
+        let kind = match string(class, "mForm")? {
+            "RF_LIQUID" | "RF_GAS" => ItemKind::Fluid,
+            _ => ItemKind::Item,
+        };
+        let stack_size = match class.get("mStackSize").and_then(Value::as_str) {
+            _ if kind == ItemKind::Fluid => None,
+            Some("SS_ONE") => Some(1),
+            Some("SS_SMALL") => Some(50),
+            Some("SS_MEDIUM") => Some(100),
+            Some("SS_BIG") => Some(200),
+            Some("SS_HUGE") => Some(500),
+            _ => None,
+        };
+        database.items.insert(
+            name.to_owned(),
+            Item {
+                name: name.to_owned(),
+                display_name: string(class, "mDisplayName")?.to_owned(),
+                kind,
+                stack_size,
+            },
+        );
+    }
+
+    for class in classes(groups, |native_class| MANUFACTURER_CLASSES.contains(&native_class)) {
+        let name = string(class, "ClassName")?;
+        let mut power = number(class, "mPowerConsumption")?;
+        // Buildings with variable power draw give a range instead
+        if power == 0.0 {
+            if let (Ok(minimum), Ok(maximum)) = (number(class, "mEstimatedMininumPowerConsumption"), number(class, "mEstimatedMaximumPowerConsumption")) {
+                power = (minimum + maximum) / 2.0;
+            }
+        }
+        database.machines.insert(
+            name.to_owned(),
+            Machine {
+                name: name.to_owned(),
+                display_name: string(class, "mDisplayName")?.to_owned(),
+                crafting_speed: number(class, "mManufacturingSpeed")?,
+                categories: vec![name.to_owned()],
+                // Power is given in megawatts
+                power: power * 1e6,
+                power_exponent: number(class, "mPowerConsumptionExponent").unwrap_or(1.0),
+                module_slots: 0,
+            },
+        );
+    }
+
+    for class in classes(groups, |native_class| native_class == "FGRecipe") {
+        let name = string(class, "ClassName")?;
+        let producers = class_names(string(class, "mProducedIn")?);
+        let crafted_in = producers.iter().find(|producer| database.machines.contains_key(*producer)).map(|building| (building, false));
+        let built_with = producers.iter().find(|producer| producer.starts_with("BP_BuildGun")).map(|build_gun| (build_gun, true));
+        let Some((category, construction)) = crafted_in.or(built_with) else {
+            continue;
+        };
+        let display_name = string(class, "mDisplayName")?;
+        let amounts = |field| -> Result<Vec<ItemAmount>, ImportError> {
+            let mut amounts = item_amounts(string(class, field)?).ok_or_else(|| invalid(name, field))?;
+            // Fluids are stored in litres, so they are converted to the cubic metres shown in game
+            for amount in amounts.iter_mut() {
+                if database.items.get(&amount.item).is_some_and(|item| item.kind == ItemKind::Fluid) {
+                    amount.amount /= 1000.0;
+                }
+            }
+            Ok(amounts)
+        };
+        let recipe = Recipe {
+            name: name.to_owned(),
+            display_name: display_name.to_owned(),
+            category: category.clone(),
+            time: number(class, "mManufactoringDuration")?,
+            ingredients: amounts("mIngredients")?,
+            results: amounts("mProduct")?,
+            alternate: name.starts_with("Recipe_Alternate_") || display_name.starts_with("Alternate:"),
+        };
+        let recipes = if construction { &mut database.construction_recipes } else { &mut database.recipes };
+        recipes.insert(name.to_owned(), recipe);
+    }
+    Ok(database)
+}
+
+/// The classes of every native class `is_wanted` accepts, given its short name
+fn classes<'a>(groups: &'a [Value], is_wanted: impl Fn(&str) -> bool + 'a) -> impl Iterator<Item = &'a Map<String, Value>> {
+    groups
+        .iter()
+        .filter(move |group| group["NativeClass"].as_str().map(short_class_name).is_some_and(&is_wanted))
+        .filter_map(|group| group["Classes"].as_array())
+        .flatten()
+        .filter_map(Value::as_object)
+}
+
+/// Decodes UTF-16 with a byte order mark, or UTF-8 with or without one
+fn decode(bytes: &[u8]) -> Result<String, ImportError> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units = bytes.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]]));
+        char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(|_| ImportError::Format("the file is not valid UTF-16".to_owned()))
+    };
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        _ => {
+            let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+            String::from_utf8(bytes.to_vec()).map_err(|_| ImportError::Format("the file is neither UTF-16 nor UTF-8".to_owned()))
+        }
+    }
+}
+
+fn invalid(class: &str, field: &str) -> ImportError {
+    ImportError::Format(format!("{class} has no valid {field}"))
+}
+
+fn string<'a>(class: &'a Map<String, Value>, field: &str) -> Result<&'a str, ImportError> {
+    let class_name = class.get("ClassName").and_then(Value::as_str).unwrap_or("a class");
+    class.get(field).and_then(Value::as_str).ok_or_else(|| invalid(class_name, field))
+}
+
+/// Numbers are stored as strings, like `"4.000000"`
+fn number(class: &Map<String, Value>, field: &str) -> Result<f64, ImportError> {
+    let class_name = class.get("ClassName").and_then(Value::as_str).unwrap_or("a class");
+    string(class, field)?.parse().map_err(|_| invalid(class_name, field))
+}
+
+/// Turns a path like `/Script/CoreUObject.Class'/Script/FactoryGame.FGRecipe'` into `FGRecipe`
+fn short_class_name(path: &str) -> &str {
+    let path = path.trim_matches(|c| matches!(c, '(' | ')' | '"' | '\'' | ' '));
+    path.rsplit('.').next().unwrap_or(path).trim_matches(|c| matches!(c, '"' | '\''))
+}
+
+/// Reads a list of classes like `("/Game/.../Build_SmelterMk1.Build_SmelterMk1_C","/Game/.../BP_WorkBenchComponent.BP_WorkBenchComponent_C")`
+fn class_names(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(short_class_name)
+        .filter(|name| !name.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Reads a list of amounts like `((ItemClass=BlueprintGeneratedClass'"/Game/.../Desc_OreIron.Desc_OreIron_C"',Amount=1))`
+/// The quoting of the class paths differs between versions of the game
+fn item_amounts(list: &str) -> Option<Vec<ItemAmount>> {
+    list.split("ItemClass=")
+        .skip(1)
+        .map(|entry| {
+            let (class, amount) = entry.split_once(",Amount=")?;
+            let amount = amount.split([')', ',']).next()?;
+            Some(ItemAmount {
+                item: short_class_name(class).to_owned(),
+                amount: amount.trim().parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::ConstructionCost;
+    use crate::report::ConstructionSettings;
+    use crate::report::NodeReport;
+    use crate::report::Report;
+
+    const FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/satisfactory-docs.json");
+
+    fn amounts(amounts: &[ItemAmount]) -> Vec<(&str, f64)> {
+        amounts.iter().map(|amount| (amount.item.as_str(), amount.amount)).collect()
+    }
+
+    #[test]
+    fn imports_items_and_fluids() {
+        let database = import(FIXTURE).unwrap();
+        let ingot = &database.items["Desc_IronIngot_C"];
+        assert_eq!(ingot.display_name, "Iron Ingot");
+        assert_eq!(ingot.kind, ItemKind::Item);
+        assert_eq!(ingot.stack_size, Some(100));
+        assert_eq!(database.items["Desc_OreIron_C"].stack_size, Some(100));
+        assert_eq!(database.items["Desc_Water_C"].kind, ItemKind::Fluid);
+        assert_eq!(database.items["Desc_Water_C"].stack_size, None);
+    }
+
+    #[test]
+    fn imports_buildings_with_their_power() {
+        let database = import(FIXTURE).unwrap();
+        let smelter = &database.machines["Build_SmelterMk1_C"];
+        assert_eq!(smelter.display_name, "Smelter");
+        assert_eq!(smelter.crafting_speed, 1.0);
+        assert_eq!(smelter.power, 4e6);
+        assert_eq!(smelter.power_exponent, 1.321929);
+        assert_eq!(smelter.categories, ["Build_SmelterMk1_C"]);
+        let accelerator = &database.machines["Build_HadronCollider_C"];
+        assert_eq!(accelerator.power, 1000e6);
+        assert_eq!(database.machines.len(), 3);
+    }
+
+    #[test]
+    fn imports_recipes_made_in_buildings() {
+        let database = import(FIXTURE).unwrap();
+        let ingot = &database.recipes["Recipe_IngotIron_C"];
+        assert_eq!(ingot.category, "Build_SmelterMk1_C");
+        assert_eq!(ingot.time, 2.0);
+        assert_eq!(amounts(&ingot.ingredients), [("Desc_OreIron_C", 1.0)]);
+        assert_eq!(amounts(&ingot.results), [("Desc_IronIngot_C", 1.0)]);
+        assert!(!ingot.alternate);
+        assert!(!database.recipes.contains_key("Recipe_WorkBenchOnly_C"));
+        assert!(!database.recipes.contains_key("Recipe_SmelterMk1_C"));
+    }
+
+    #[test]
+    fn keeps_build_gun_recipes_for_construction() {
+        let database = import(FIXTURE).unwrap();
+        let smelter = &database.construction_recipes["Recipe_SmelterMk1_C"];
+        assert_eq!(smelter.category, "BP_BuildGun_C");
+        assert_eq!(amounts(&smelter.results), [("Desc_SmelterMk1_C", 1.0)]);
+        assert_eq!(database.construction_recipes.len(), 1);
+
+        let smelting = NodeReport { buildings: vec![("Smelter".to_owned(), 1.5)], ..Default::default() };
+        let cost = ConstructionCost::new(&Report::new([smelting], 0.0, 0.0), &database, &ConstructionSettings::default());
+        assert_eq!(cost.pieces, [("Smelter".to_owned(), 2.0)]);
+        assert_eq!(cost.materials, [("Iron Ingot".to_owned(), 10.0)]);
+        assert!(cost.unknown.is_empty());
+    }
+
+    #[test]
+    fn flags_alternate_recipes() {
+        let database = import(FIXTURE).unwrap();
+        let pure = &database.recipes["Recipe_Alternate_PureIronIngot_C"];
+        assert!(pure.alternate);
+        assert_eq!(pure.category, "Build_OilRefinery_C");
+        assert_eq!(amounts(&pure.ingredients), [("Desc_OreIron_C", 7.0), ("Desc_Water_C", 4.0)]);
+        assert_eq!(amounts(&pure.results), [("Desc_IronIngot_C", 13.0)]);
+    }
+
+    #[test]
+    fn reads_utf8_as_well() {
+        let text = decode(FIXTURE).unwrap();
+        assert_eq!(import(text.as_bytes()).unwrap(), import(FIXTURE).unwrap());
+    }
+
+    #[test]
+    fn decodes_both_byte_orders() {
+        assert_eq!(decode(&[0xFF, 0xFE, b'[', 0, b']', 0]).unwrap(), "[]");
+        assert_eq!(decode(&[0xFE, 0xFF, 0, b'[', 0, b']']).unwrap(), "[]");
+        assert!(decode(&[0xFF, 0xFE, 0x00, 0xD8]).is_err());
+    }
+
+    #[test]
+    fn reads_amount_lists() {
+        let list = r#"((ItemClass=BlueprintGeneratedClass'"/Game/FactoryGame/Resource/Parts/IronIngot/Desc_IronIngot.Desc_IronIngot_C"',Amount=3),(ItemClass=/Game/FactoryGame/Resource/RawResources/Coal/Desc_Coal.Desc_Coal_C,Amount=2))"#;
+        let amounts = item_amounts(list).unwrap();
+        assert_eq!(amounts[0].item, "Desc_IronIngot_C");
+        assert_eq!(amounts[0].amount, 3.0);
+        assert_eq!(amounts[1].item, "Desc_Coal_C");
+        assert_eq!(amounts[1].amount, 2.0);
+        assert!(item_amounts("((ItemClass=Desc_Coal_C,Amount=lots))").is_none());
+    }
+}
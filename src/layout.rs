@@ -0,0 +1,255 @@
+use eframe::egui::Pos2;
+use eframe::egui::Vec2;
+
+/// How many times the layers are swept over when reducing link crossings
+const ORDERING_SWEEPS: usize = 24;
+
+/// Lays out a directed graph in layers from left to right, in the style of Sugiyama et al.
+/// `sizes` holds the size of each node, and `edges` the (from, to) pairs of node indices
+/// `gap` is the space left between layers horizontally, and between nodes in a layer vertically
+/// Returns the top left position of each node, with the layout starting at the origin
+pub fn layered_layout(sizes: &[Vec2], edges: &[(usize, usize)], gap: Vec2) -> Vec<Pos2> {
+    let node_count = sizes.len();
+    let edges = acyclic_edges(node_count, edges);
+    let mut layer_of = assign_layers(node_count, &edges);
+
+    // Edges spanning several layers get a dummy node in every layer they pass through,
+    // so they take part in crossing reduction like any other edge
+    let mut proper_edges = Vec::new();
+    for (from, to) in edges {
+        let mut previous = from;
+        for layer in layer_of[from] + 1..layer_of[to] {
+            let dummy = layer_of.len();
+            layer_of.push(layer);
+            proper_edges.push((previous, dummy));
+            previous = dummy;
+        }
+        proper_edges.push((previous, to));
+    }
+
+    let layer_count = layer_of.iter().max().map_or(0, |layer| layer + 1);
+    let mut layers = vec![Vec::new(); layer_count];
+    for (node, layer) in layer_of.iter().enumerate() {
+        layers[*layer].push(node);
+    }
+    order_layers(&mut layers, &proper_edges, layer_of.len());
+
+    let size_of = |node: usize| sizes.get(node).copied().unwrap_or(Vec2::ZERO);
+    let layer_heights: Vec<f32> = layers
+        .iter()
+        .map(|layer| layer.iter().map(|node| size_of(*node).y + gap.y).sum::<f32>() - gap.y)
+        .collect();
+    let total_height = layer_heights.iter().copied().fold(0.0, f32::max);
+    let mut positions = vec![Pos2::ZERO; node_count];
+    let mut x = 0.0;
+    for (layer, layer_height) in layers.iter().zip(layer_heights) {
+        let mut y = (total_height - layer_height) / 2.0;
+        for node in layer {
+            if *node < node_count {
+                positions[*node] = Pos2::new(x, y);
+            }
+            y += size_of(*node).y + gap.y;
+        }
+        x += layer.iter().map(|node| size_of(*node).x).fold(0.0, f32::max) + gap.x;
+    }
+    positions
+}
+
+/// Reverses edges that close a cycle, found with a depth first search, and drops self loops
+fn acyclic_edges(node_count: usize, edges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut successors = vec![Vec::new(); node_count];
+    for (index, (from, to)) in edges.iter().enumerate() {
+        successors[*from].push((*to, index));
+    }
+    // 0 is unvisited, 1 is on the current search path, 2 is finished
+    let mut state = vec![0u8; node_count];
+    let mut reversed = vec![false; edges.len()];
+    for root in 0..node_count {
+        if state[root] != 0 {
+            continue;
+        }
+        state[root] = 1;
+        let mut stack = vec![(root, 0)];
+        while let Some((node, next_successor)) = stack.last_mut() {
+            let node = *node;
+            if let Some((successor, edge)) = successors[node].get(*next_successor).copied() {
+                *next_successor += 1;
+                match state[successor] {
+                    0 => {
+                        state[successor] = 1;
+                        stack.push((successor, 0));
+                    }
+                    1 => reversed[edge] = true,
+                    _ => {}
+                }
+            } else {
+                state[node] = 2;
+                stack.pop();
+            }
+        }
+    }
+    edges
+        .iter()
+        .zip(reversed)
+        .filter(|((from, to), _)| from != to)
+        .map(|((from, to), reversed)| if reversed { (*to, *from) } else { (*from, *to) })
+        .collect()
+}
+
+/// Puts every node one layer after the furthest of its predecessors, so sources end up in the first layer
+/// `edges` must not contain cycles
+fn assign_layers(node_count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut successors = vec![Vec::new(); node_count];
+    let mut remaining_predecessors = vec![0; node_count];
+    for (from, to) in edges {
+        successors[*from].push(*to);
+        remaining_predecessors[*to] += 1;
+    }
+    let mut layer_of = vec![0; node_count];
+    let mut ready: Vec<_> = (0..node_count).filter(|node| remaining_predecessors[*node] == 0).collect();
+    while let Some(node) = ready.pop() {
+        for successor in successors[node].iter() {
+            layer_of[*successor] = layer_of[*successor].max(layer_of[node] + 1);
+            remaining_predecessors[*successor] -= 1;
+            if remaining_predecessors[*successor] == 0 {
+                ready.push(*successor);
+            }
+        }
+    }
+    layer_of
+}
+
+/// Reorders the nodes inside each layer to reduce crossing edges, using the barycenter heuristic
+/// Edges must only go from one layer to the next
+fn order_layers(layers: &mut [Vec<usize>], edges: &[(usize, usize)], node_count: usize) {
+    let mut predecessors = vec![Vec::new(); node_count];
+    let mut successors = vec![Vec::new(); node_count];
+    for (from, to) in edges {
+        successors[*from].push(*to);
+        predecessors[*to].push(*from);
+    }
+    let mut order = vec![0.0; node_count];
+    for layer in layers.iter() {
+        for (index, node) in layer.iter().enumerate() {
+            order[*node] = index as f32;
+        }
+    }
+    let mut best = layers.to_vec();
+    let mut best_crossings = count_crossings(layers, &successors, &order);
+    for sweep in 0..ORDERING_SWEEPS {
+        let downwards = sweep % 2 == 0;
+        let layer_indices: Vec<_> = if downwards {
+            (1..layers.len()).collect()
+        } else {
+            (0..layers.len().saturating_sub(1)).rev().collect()
+        };
+        for layer_index in layer_indices {
+            let neighbours = if downwards { &predecessors } else { &successors };
+            let barycenters: Vec<_> = layers[layer_index]
+                .iter()
+                .map(|node| {
+                    let neighbours = &neighbours[*node];
+                    if neighbours.is_empty() {
+                        order[*node]
+                    } else {
+                        neighbours.iter().map(|neighbour| order[*neighbour]).sum::<f32>() / neighbours.len() as f32
+                    }
+                })
+                .collect();
+            let mut sorted: Vec<_> = layers[layer_index].iter().copied().zip(barycenters).collect();
+            sorted.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+            layers[layer_index] = sorted.into_iter().map(|(node, _)| node).collect();
+            for (index, node) in layers[layer_index].iter().enumerate() {
+                order[*node] = index as f32;
+            }
+        }
+        let crossings = count_crossings(layers, &successors, &order);
+        if crossings < best_crossings {
+            best_crossings = crossings;
+            best = layers.to_vec();
+        }
+    }
+    layers.clone_from_slice(&best);
+}
+
+fn count_crossings(layers: &[Vec<usize>], successors: &[Vec<usize>], order: &[f32]) -> usize {
+    let mut crossings = 0;
+    for layer in layers {
+        let edges: Vec<_> = layer
+            .iter()
+            .flat_map(|node| successors[*node].iter().map(move |successor| (order[*node], order[*successor])))
+            .collect();
+        for (index, (from_a, to_a)) in edges.iter().enumerate() {
+            for (from_b, to_b) in edges[index + 1..].iter() {
+                if (from_a < from_b && to_a > to_b) || (from_a > from_b && to_a < to_b) {
+                    crossings += 1;
+                }
+            }
+        }
+    }
+    crossings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that every edge goes from an earlier layer to a later one
+    fn assert_layered(edges: &[(usize, usize)], layer_of: &[usize]) {
+        for (from, to) in edges {
+            assert!(layer_of[*from] < layer_of[*to], "{from} -> {to} in layers {layer_of:?}");
+        }
+    }
+
+    #[test]
+    fn reverses_the_edge_closing_a_cycle() {
+        let edges = acyclic_edges(3, &[(0, 1), (1, 2), (2, 0)]);
+        assert_eq!(edges, vec![(0, 1), (1, 2), (0, 2)]);
+        assert_layered(&edges, &assign_layers(3, &edges));
+    }
+
+    #[test]
+    fn breaks_every_cycle_and_drops_self_loops() {
+        let edges = [(0, 0), (0, 1), (1, 0), (1, 2), (2, 3), (3, 1), (3, 3), (4, 2)];
+        let acyclic = acyclic_edges(5, &edges);
+        assert_eq!(acyclic.len(), edges.len() - 2);
+        assert!(acyclic.iter().all(|(from, to)| from != to));
+        assert_layered(&acyclic, &assign_layers(5, &acyclic));
+    }
+
+    #[test]
+    fn layers_follow_the_longest_path() {
+        let edges = [(0, 1), (1, 2), (0, 2), (3, 2)];
+        assert_eq!(assign_layers(4, &edges), vec![0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn ordering_removes_crossings() {
+        let mut layers = vec![vec![0, 1], vec![2, 3]];
+        let edges = [(0, 3), (1, 2)];
+        order_layers(&mut layers, &edges, 4);
+        let mut order = vec![0.0; 4];
+        for layer in layers.iter() {
+            for (index, node) in layer.iter().enumerate() {
+                order[*node] = index as f32;
+            }
+        }
+        assert_eq!(count_crossings(&layers, &[vec![3], vec![2], vec![], vec![]], &order), 0);
+    }
+
+    #[test]
+    fn lays_out_cycles_from_left_to_right() {
+        let sizes = vec![Vec2::new(100.0, 50.0); 4];
+        let gap = Vec2::new(20.0, 10.0);
+        // A loop back from the last node, like a recycled byproduct, and a link spanning two layers
+        let positions = layered_layout(&sizes, &[(0, 1), (1, 2), (2, 1), (0, 3), (2, 3)], gap);
+        assert_eq!(positions.len(), 4);
+        assert_eq!(positions.iter().map(|pos| pos.x).collect::<Vec<_>>(), vec![0.0, 120.0, 240.0, 360.0]);
+        assert!(positions.iter().all(|pos| pos.y >= 0.0 && pos.y.is_finite()));
+    }
+
+    #[test]
+    fn lays_out_nothing() {
+        assert!(layered_layout(&[], &[], Vec2::splat(10.0)).is_empty());
+    }
+}
@@ -4,6 +4,18 @@ mod node_graph;
 mod node;
 mod nodes;
 mod createable_node;
+mod serialization;
+mod layout;
+mod flow;
+mod modules;
+mod importers;
+mod recipe_database;
+mod report;
+mod palette;
+mod shortcuts;
+mod theme;
+
+use std::rc::Rc;
 
 use eframe::egui;
 use eframe::egui::Response;
@@ -17,9 +29,17 @@ use nodes::graph_node::GraphNode;
 use crate::node::Node;
 use crate::node_graph::NodeGraph;
 use crate::nodes::adder_node::AdderNode;
+use crate::nodes::generator_node::GeneratorNode;
+use crate::nodes::machine_node::MachineNode;
 use crate::nodes::one_to_n_node::OneToNNode;
+use crate::nodes::pump_node::PumpNode;
 use crate::nodes::sink_node::SinkNode;
 use crate::nodes::source_node::SourceNode;
+use crate::nodes::tank_node::TankNode;
+use crate::nodes::void_node::VoidNode;
+use crate::modules::ModuleDatabase;
+use crate::recipe_database::RecipeDatabase;
+use crate::shortcuts::ShortcutMap;
 
 /// Selecting text is currently broken under a TSTransform, 
 /// so this is a shortcut to prevent it in labels
@@ -43,15 +63,113 @@ impl Node for DebugNode {
 }
 
 
+/// The value of a result in a tool run from the command line, or exits with an error code after printing what went wrong
+fn or_exit<T>(result: Result<T, impl std::fmt::Display>, context: &str) -> T {
+    result.unwrap_or_else(|error| {
+        eprintln!("{context}: {error}");
+        std::process::exit(1);
+    })
+}
+
 fn main() -> eframe::Result<()> {
-    let mut graph = NodeGraph::new("test");
+    let mut graph = NodeGraph::new("test").enable_inspector_panel().enable_power_panel().enable_surplus_panel().enable_report_panel();
     graph.selector_panel_enabled = true;
-    graph.register_node(DebugNode::default());
-    graph.register_node(SourceNode::default());
-    graph.register_node(SinkNode::default());
-    graph.register_node(AdderNode::default());
-    graph.register_node(OneToNNode::default());
-    graph.register_node_with_id::<GraphNode>();
+    graph.minimap_enabled = true;
+    graph.register_node(DebugNode::default(), "Debug", &["test"]);
+    graph.register_node(SourceNode::default(), "Values", &["input", "constant", "number"]);
+    graph.register_node(SinkNode::default(), "Values", &["output", "display", "result"]);
+    graph.register_node(AdderNode::default(), "Math", &["add", "sum", "plus"]);
+    graph.register_node(OneToNNode::default(), "Logistics", &["split", "splitter", "fan out"]);
+    // Machines use the recipe database in `recipes.json` next to where the program is run, made by one of the importers
+    let database = match std::fs::read_to_string("recipes.json").map(|text| RecipeDatabase::load(&text)) {
+        Ok(Ok(database)) => database,
+        Ok(Err(error)) => {
+            eprintln!("ignoring recipes.json, {error}");
+            RecipeDatabase::default()
+        }
+        Err(_) => RecipeDatabase::default(),
+    };
+    // and the modules in `modules.json`, which comes with the modules of Factorio
+    let module_database = match std::fs::read_to_string("modules.json").map(|text| ModuleDatabase::load(&text)) {
+        Ok(Ok(module_database)) => module_database,
+        Ok(Err(error)) => {
+            eprintln!("ignoring modules.json, {error}");
+            ModuleDatabase::default()
+        }
+        Err(_) => ModuleDatabase::default(),
+    };
+    let database = Rc::new(database);
+    graph = graph.with_recipe_database(database.clone());
+    graph.register_node(MachineNode::new(database.clone(), Rc::new(module_database), graph.alternate_recipes()), "Production", &["recipe", "factory", "building", "assembler", "overclock"]);
+    graph.register_node(GeneratorNode::default(), "Power", &["steam engine", "solar panel", "electricity", "energy"]);
+    graph.register_node(TankNode::new(database.clone()), "Fluids", &["storage", "buffer", "pool"]);
+    graph.register_node(PumpNode::default(), "Fluids", &["pipe", "pipeline", "throughput"]);
+    graph.register_node(VoidNode::new(database), "Production", &["sink", "excess", "surplus", "byproduct", "trash"]);
+    graph.register_node_with_id::<GraphNode>("Graph", &["subgraph", "nested", "group"]);
+    // Shortcuts can be changed with a `shortcuts.txt` next to where the program is run, see `ShortcutMap::parse`
+    match std::fs::read_to_string("shortcuts.txt").map(|text| ShortcutMap::parse(&text)) {
+        Ok(Ok(shortcuts)) => graph.shortcuts = shortcuts,
+        Ok(Err(error)) => eprintln!("ignoring shortcuts.txt, {error}"),
+        Err(_) => {}
+    }
+    // Tools that run without opening a window:
+    // `factory_designer --layout <input> <output>` lays out a saved graph
+    // `factory_designer --report <input> <output>` writes the bill of materials of a saved graph, as Markdown if the output ends in `.md` and CSV otherwise
+    // `factory_designer --construction-cost <input> <output>` writes the materials for building a saved graph the same way,
+    // and `--raw-construction-cost` goes down to raw resources
+    // `factory_designer --import-factorio <data-raw-dump.json> <output>` turns Factorio's data into a recipe database
+    // `factory_designer --import-satisfactory <Docs.json> <output>` does the same for Satisfactory's data
+    if let [_, flag, input, output] = std::env::args().collect::<Vec<_>>().as_slice() {
+        match flag.as_str() {
+            "--layout" => {
+                let text = or_exit(std::fs::read_to_string(input), &format!("failed to read {input}"));
+                or_exit(graph.load(&text), &format!("{input} is not a saved graph"));
+                graph.auto_layout(false);
+                or_exit(std::fs::write(output, graph.save()), &format!("failed to write {output}"));
+                return Ok(());
+            }
+            "--report" => {
+                let text = or_exit(std::fs::read_to_string(input), &format!("failed to read {input}"));
+                or_exit(graph.load(&text), &format!("{input} is not a saved graph"));
+                let report = graph.report();
+                let text = if output.ends_with(".md") { report.to_markdown() } else { report.to_csv() };
+                or_exit(std::fs::write(output, text), &format!("failed to write {output}"));
+                return Ok(());
+            }
+            "--construction-cost" | "--raw-construction-cost" => {
+                let text = or_exit(std::fs::read_to_string(input), &format!("failed to read {input}"));
+                or_exit(graph.load(&text), &format!("{input} is not a saved graph"));
+                graph.construction_settings.recursive = flag == "--raw-construction-cost";
+                let cost = or_exit(graph.construction_cost().ok_or("the graph has no recipe database"), "failed to work out the construction cost");
+                let text = if output.ends_with(".md") { cost.to_markdown() } else { cost.to_csv() };
+                or_exit(std::fs::write(output, text), &format!("failed to write {output}"));
+                return Ok(());
+            }
+            "--import-factorio" => {
+                let text = std::fs::read_to_string(input).expect("failed to read the Factorio data");
+                match importers::factorio::import(&text) {
+                    Ok(database) => std::fs::write(output, database.save()).expect("failed to write the recipe database"),
+                    Err(error) => {
+                        eprintln!("failed to import {input}: {error}");
+                        std::process::exit(1);
+                    }
+                }
+                return Ok(());
+            }
+            "--import-satisfactory" => {
+                let bytes = std::fs::read(input).expect("failed to read the Satisfactory data");
+                match importers::satisfactory::import(&bytes) {
+                    Ok(database) => std::fs::write(output, database.save()).expect("failed to write the recipe database"),
+                    Err(error) => {
+                        eprintln!("failed to import {input}: {error}");
+                        std::process::exit(1);
+                    }
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
     eframe::run_simple_native("app_name", NativeOptions::default(), move |ctx, _frame| {
         graph.show(ctx);
     })
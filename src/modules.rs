@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Changes to how a machine crafts, as fractions added on top of the normal values
+/// A speed of 0.5 makes the machine craft 50% faster
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Effect {
+    pub speed: f64,
+    /// Extra results made for free, on top of the normal results
+    pub productivity: f64,
+    /// Extra energy used, negative for less
+    pub consumption: f64,
+}
+
+impl Effect {
+    fn add(self, other: Effect, scale: f64) -> Effect {
+        Effect {
+            speed: self.speed + other.speed * scale,
+            productivity: self.productivity + other.productivity * scale,
+            consumption: self.consumption + other.consumption * scale,
+        }
+    }
+
+    /// Describes the non zero parts of the effect, like `+40% speed, -30% energy`
+    pub fn describe(&self) -> String {
+        let parts: Vec<_> = [(self.speed, "speed"), (self.productivity, "productivity"), (self.consumption, "energy")]
+            .into_iter()
+            .filter(|(value, _)| value.abs() > 1e-9)
+            .map(|(value, name)| format!("{:+.0}% {name}", value * 100.0))
+            .collect();
+        if parts.is_empty() {
+            "No effect".to_owned()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// A module that can be put into machines or beacons
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Module {
+    pub name: String,
+    pub display_name: String,
+    pub effect: Effect,
+    /// The only recipes the module can be used with, any recipe if empty
+    #[serde(default)]
+    pub limitation: Vec<String>,
+}
+
+impl Module {
+    pub fn allowed_for(&self, recipe: &str) -> bool {
+        self.limitation.is_empty() || self.limitation.iter().any(|allowed| allowed == recipe)
+    }
+
+    /// Beacons can't pass on productivity
+    pub fn allowed_in_beacons(&self) -> bool {
+        self.effect.productivity <= 0.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Beacon {
+    /// How much of the effect of its modules a beacon passes on to each machine in range
+    pub distribution_effectivity: f64,
+    pub module_slots: u32,
+}
+
+impl Default for Beacon {
+    fn default() -> Self {
+        Self {
+            distribution_effectivity: 0.5,
+            module_slots: 2,
+        }
+    }
+}
+
+/// The modules and beacon of a game, read from a local data file
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModuleDatabase {
+    #[serde(default)]
+    pub beacon: Beacon,
+    /// Keyed by the internal names of the modules
+    pub modules: BTreeMap<String, Module>,
+}
+
+impl ModuleDatabase {
+    /// Reads a data file like the `modules.json` at the root of the repository
+    pub fn load(text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+
+    /// The effect on a machine crafting `recipe` with `modules` inside it and `beacons` in range,
+    /// each of the beacons holding `beacon_modules`
+    /// Effects of all modules add up, with the ones in beacons scaled by the beacon's distribution effectivity,
+    /// then speed and energy are kept from going below -80% and productivity from going below 0
+    /// Modules that can't be used for the recipe, or that can't go in beacons, are ignored
+    pub fn effect(&self, recipe: &str, modules: &[String], beacons: u32, beacon_modules: &[String]) -> Effect {
+        let find = |name: &String| self.modules.get(name).filter(|module| module.allowed_for(recipe));
+        let mut effect = modules.iter().filter_map(find).fold(Effect::default(), |effect, module| effect.add(module.effect, 1.0));
+        let beacon_scale = self.beacon.distribution_effectivity * beacons as f64;
+        for module in beacon_modules.iter().filter_map(find).filter(|module| module.allowed_in_beacons()) {
+            effect = effect.add(module.effect, beacon_scale);
+        }
+        Effect {
+            speed: effect.speed.max(-0.8),
+            productivity: effect.productivity.max(0.0),
+            consumption: effect.consumption.max(-0.8),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULES: &str = include_str!("../modules.json");
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| (*name).to_owned()).collect()
+    }
+
+    #[test]
+    fn adds_up_machine_and_beacon_modules() {
+        let database = ModuleDatabase::load(MODULES).unwrap();
+        let effect = database.effect("iron-gear-wheel", &names(&["productivity-module-3", "productivity-module-3"]), 4, &names(&["speed-module-3", "speed-module-3"]));
+        assert!((effect.productivity - 0.2).abs() < 1e-9);
+        // 2 * -0.15 from the productivity modules, 4 beacons * 0.5 * 2 * 0.5 from the speed modules
+        assert!((effect.speed - 1.7).abs() < 1e-9);
+        assert!((effect.consumption - 4.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn limits_speed_and_energy_reductions() {
+        let database = ModuleDatabase::load(MODULES).unwrap();
+        let effect = database.effect("iron-gear-wheel", &names(&["effectivity-module-3"; 4]), 0, &[]);
+        assert_eq!(effect.consumption, -0.8);
+        let effect = database.effect("iron-gear-wheel", &names(&["productivity-module-3"; 8]), 0, &[]);
+        assert_eq!(effect.speed, -0.8);
+    }
+
+    #[test]
+    fn ignores_modules_that_are_not_allowed() {
+        let database = ModuleDatabase::load(MODULES).unwrap();
+        let effect = database.effect("stone-wall", &names(&["productivity-module"]), 0, &[]);
+        assert_eq!(effect, Effect::default());
+        let effect = database.effect("iron-gear-wheel", &[], 1, &names(&["productivity-module"]));
+        assert_eq!(effect, Effect::default());
+    }
+}
@@ -4,38 +4,133 @@ use std::any::TypeId;
 use dyn_clone::clone_trait_object;
 use dyn_clone::DynClone;
 use eframe::egui;
-use eframe::egui::Color32;
+use eframe::egui::epaint::RectShape;
 use eframe::egui::Pos2;
+use eframe::egui::Rect;
+use eframe::egui::RichText;
+use eframe::egui::Rounding;
+use eframe::egui::Sense;
+use eframe::egui::Shape;
+use eframe::egui::Stroke;
 use eframe::egui::Ui;
 use eframe::egui::Vec2;
 
+use crate::flow::Demand;
+use crate::flow::ItemFlow;
 use crate::node_input::NodeInput;
 use crate::node_output::NodeOutput;
+use crate::report::NodeReport;
+use crate::theme::NodeStyle;
+use crate::theme::Theme;
 use crate::unselectable_label;
 
+/// How a node is displayed, kept by the graph for each node and saved along with it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NodeDisplayState {
+    /// Hides the body and the port labels, leaving the title and the ports stacked at the edges
+    pub collapsed: bool,
+    /// The size the body was resized to, `None` lets the body pick its own size
+    pub body_size: Option<Vec2>,
+}
 
 pub trait Node: DynClone {
     /// The title to display for the node
     fn title(&self) -> &str;
     /// A body is made of three things: a list of inputs, the central ui, a list of outputs
-    /// each input/output can have 2 attached FnOnces: a ui display, and a value callback
+    /// each input/output can have 2 attached callbacks: a ui display, and a value callback
     /// The ui display is normal |ui| {}
     /// The callback is created via a function fn on_connect_input/output<T> where T is the type input output
     /// Connecting two connectors together is only possible if they share the same value
     /// values are passed as Box<dyn Any> and downcast is used to check if a connection is possible
     fn body<'a>(&'a mut self) -> (Vec<NodeInput>, Box<dyn FnOnce(&mut Ui) + 'a>, Vec<NodeOutput>);
+    /// Identifies the type of the node in saved data, so the matching registered node can be found when loading
+    /// Defaults to the rust type name
+    fn type_name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+    /// Saves the state of the node, to be restored with `load` on a freshly created node of the same type
+    /// Nodes without any state to keep can use the default, which saves nothing
+    fn save(&self) -> String {
+        String::new()
+    }
+    /// Restores the state saved by `save`
+    fn load(&mut self, _data: &str) {}
+    /// Whether the default `show` gives the node a handle for resizing its body
+    /// Meant for nodes with bodies that can make use of more space, like a nested graph
+    fn resizable(&self) -> bool {
+        false
+    }
+    /// Shows the full editable properties of the node in the graph's inspector panel
+    /// Unlike `body`, this is called at most once a frame and only for the inspected node,
+    /// so it can show more than fits in the node itself
+    fn inspector(&mut self, ui: &mut Ui) {
+        ui.weak("No properties to edit");
+    }
+    /// Problems with the node to show in the inspector, such as missing inputs or invalid settings
+    fn errors(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// The power the node draws, in watts, counted in the graph's power summary
+    fn power_draw(&self) -> f64 {
+        0.0
+    }
+    /// The power the node generates, in watts, counted in the graph's power summary
+    fn power_supply(&self) -> f64 {
+        0.0
+    }
+    /// What the node makes through an output, used by the graph to track surplus
+    /// Fluids are given as `ItemFlow`s too
+    fn output_supply(&self, _index: usize) -> Option<ItemFlow> {
+        None
+    }
+    /// What the node needs through an input, used by the graph to track the surplus of the outputs linked to it
+    /// Inputs without a demand, like ones passing on whatever they are given, keep the outputs linked to them from having surplus
+    fn input_demand(&self, _index: usize) -> Option<Demand> {
+        None
+    }
+    /// What the node brings in, makes, uses and needs to be built, counted in the graph's bill of materials
+    fn report(&self) -> NodeReport {
+        NodeReport::default()
+    }
+    /// How the default `show` should draw this type of node, on top of the graph's theme
+    fn style(&self) -> NodeStyle {
+        NodeStyle::default()
+    }
     /// The method used to display the node
     /// Contains a default implementation that should cover most use cases
-    /// Returns a Vec for the types and locations of inputs and outputs to
+    /// `state` holds whether the node is collapsed and the size of its body, and can be changed by the node's ui
+    /// `theme` is the look of the graph the node is in
+    /// Returns a Vec for the types, type names and locations of inputs and outputs to
     /// be used by the NodeGraph for connection handling
-    fn show<'a, 'b, 'c: 'a + 'b>(&'c mut self, ui: &mut Ui) -> (Vec<(TypeId, Pos2, Box<dyn FnOnce(Box<dyn Any>) + 'a>)>, Vec<(TypeId, Pos2, Box<dyn FnOnce() -> Box<dyn Any> + 'b>)>) {
-        egui::Frame::default()
-            .inner_margin(8.0)
-            .fill(ui.style().visuals.window_fill)
-            .stroke(ui.ctx().style().visuals.window_stroke)
+    fn show<'a, 'b, 'c: 'a + 'b>(&'c mut self, ui: &mut Ui, state: &mut NodeDisplayState, theme: &Theme) -> (Vec<(TypeId, &'static str, Pos2, Box<dyn FnOnce(Box<dyn Any>) + 'a>)>, Vec<(TypeId, &'static str, Pos2, Box<dyn Fn() -> Box<dyn Any> + 'b>)>) {
+        let resizable = self.resizable();
+        let style = self.style();
+        let port_shape = style.port_shape.unwrap_or(theme.port_shape);
+        ui.style_mut().visuals = theme.visuals.clone();
+        let mut body_rect = Rect::NOTHING;
+        let mut title_rect = Rect::NOTHING;
+        // The header is painted once the width of the node is known, behind the title
+        let mut header_shape = None;
+        let frame_response = egui::Frame::default()
+            .inner_margin(theme.inner_margin)
+            .rounding(theme.visuals.window_rounding)
+            .fill(theme.visuals.window_fill)
+            .stroke(theme.visuals.window_stroke)
             .show(ui, |ui| {
-                unselectable_label(ui, self.title());
-                ui.separator();
+                header_shape = Some(ui.painter().add(Shape::Noop));
+                title_rect = ui.horizontal(|ui| {
+                    let (_, response) = ui.allocate_exact_size(Vec2::splat(ui.spacing().icon_width), Sense::click());
+                    egui::collapsing_header::paint_default_icon(ui, if state.collapsed { 0.0 } else { 1.0 }, &response);
+                    if response.clicked() {
+                        state.collapsed = !state.collapsed;
+                    }
+                    if let Some(icon) = &style.icon {
+                        unselectable_label(ui, icon.as_str());
+                    }
+                    unselectable_label(ui, RichText::new(self.title()).strong());
+                }).response.rect;
+                let title_width = title_rect.width();
+                ui.add_space(theme.inner_margin);
                 ui.horizontal(|ui| {
                     let (inputs, body, outputs) = self.body();
                     let mut input_positions = Vec::new();
@@ -44,28 +139,66 @@ pub trait Node: DynClone {
                             ui.horizontal(|ui| {
                                 let (_, rect) = ui.allocate_space(Vec2::new(10.0, 10.0));
                                 let input_position = rect.left_top() + Vec2::new(5.0, 5.0);
-                                ui.painter_at(rect).circle_filled(input_position, 5.0, Color32::BLUE);
-                                input_positions.push((input.input_type, input_position, input.input_callback));
-                                (input.ui_callback)(ui);
+                                port_shape.paint(&ui.painter_at(rect), input_position, theme.input_color);
+                                input_positions.push((input.input_type, input.type_name, input_position, input.input_callback));
+                                if !state.collapsed {
+                                    (input.ui_callback)(ui);
+                                }
                             });
                         }
                     });
-                    ui.add_enabled_ui(true, body);
+                    if state.collapsed {
+                        // Pushes the outputs over to the right edge of the title
+                        ui.add_space(title_width - 20.0 - ui.spacing().item_spacing.x * 2.0);
+                    } else {
+                        body_rect = ui.vertical(|ui| {
+                            if let Some(body_size) = state.body_size {
+                                ui.set_min_size(body_size);
+                                ui.set_max_size(body_size);
+                            }
+                            ui.add_enabled_ui(true, body);
+                        }).response.rect;
+                    }
                     let mut output_positions = Vec::new();
                     ui.vertical(|ui| {
                         for output in outputs {
                             ui.horizontal(|ui| {
-                                (output.ui_callback)(ui);
+                                if !state.collapsed {
+                                    (output.ui_callback)(ui);
+                                }
                                 let (_, rect) = ui.allocate_space(Vec2::new(10.0, 10.0));
                                 let output_position = rect.left_top() + Vec2::new(5.0, 5.0);
-                                ui.painter_at(rect).circle_filled(output_position, 5.0, Color32::RED);
-                                output_positions.push((output.output_type, output_position, output.output_callback));
+                                port_shape.paint(&ui.painter_at(rect), output_position, theme.output_color);
+                                output_positions.push((output.output_type, output.type_name, output_position, output.output_callback));
                             });
                         }
                     });
                     (input_positions, output_positions)
                 }).inner
-            }).inner
+            });
+        if let Some(header_shape) = header_shape {
+            let node_rect = frame_response.response.rect.shrink(theme.visuals.window_stroke.width);
+            let header_rect = Rect::from_min_max(node_rect.min, Pos2::new(node_rect.max.x, title_rect.max.y + theme.inner_margin / 2.0));
+            let rounding = theme.visuals.window_rounding;
+            let rounding = Rounding { sw: 0.0, se: 0.0, ..rounding };
+            ui.painter().set(header_shape, RectShape::filled(header_rect, rounding, theme.header_color(&style)));
+        }
+        if resizable && !state.collapsed {
+            let corner = frame_response.response.rect.max;
+            let handle_rect = Rect::from_min_max(corner - Vec2::splat(12.0), corner);
+            let response = ui.interact(handle_rect, ui.id().with("resize handle"), Sense::drag());
+            for offset in [4.0, 8.0] {
+                ui.painter().line_segment(
+                    [handle_rect.right_top() + Vec2::new(-2.0, offset), handle_rect.left_bottom() + Vec2::new(offset, -2.0)],
+                    Stroke::new(1.0, ui.visuals().strong_text_color()),
+                );
+            }
+            if response.dragged() {
+                let body_size = state.body_size.unwrap_or(body_rect.size());
+                state.body_size = Some((body_size + response.drag_delta()).max(Vec2::splat(40.0)));
+            }
+        }
+        frame_response.inner
     }
 }
 
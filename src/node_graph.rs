@@ -1,31 +1,351 @@
 use std::any::TypeId;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::rc::Rc;
 
 use eframe::egui;
+use eframe::egui::accesskit::Role;
+use eframe::egui::Align2;
 use eframe::egui::Color32;
+use eframe::egui::epaint::CubicBezierShape;
 use eframe::egui::Context;
+use eframe::egui::Event;
+use eframe::egui::FontId;
 use eframe::egui::Id;
+use eframe::egui::Key;
 use eframe::egui::LayerId;
+use eframe::egui::Modifiers;
 use eframe::egui::Pos2;
 use eframe::egui::Rect;
 use eframe::egui::Sense;
+use eframe::egui::Stroke;
 use eframe::egui::Ui;
 use eframe::egui::Vec2;
+use eframe::egui::WidgetInfo;
+use eframe::egui::WidgetType;
 use eframe::emath::TSTransform;
 use slotmap::new_key_type;
 use slotmap::SlotMap;
 
 use crate::createable_node::CreatableNode;
+use crate::flow::format_power;
+use crate::flow::format_rate;
+use crate::flow::pipe_throughput;
+use crate::flow::split_flow;
+use crate::flow::Demand;
+use crate::flow::FluidFlow;
+use crate::flow::ItemFlow;
+use crate::flow::DEFAULT_PIPE_LENGTH;
+use crate::flow::MAX_PIPE_LENGTH;
+use crate::layout;
+use crate::palette;
+use crate::recipe_database::RecipeDatabase;
+use crate::report::ConstructionCost;
+use crate::report::ConstructionSettings;
+use crate::report::NodeReport;
+use crate::report::Report;
+use crate::serialization::SerializedBookmark;
+use crate::serialization::SerializedComment;
+use crate::serialization::SerializedGraph;
+use crate::serialization::SerializedLink;
+use crate::serialization::SerializedNode;
+use crate::serialization::SerializedNodes;
+use crate::shortcuts::Action;
+use crate::shortcuts::ShortcutMap;
+use crate::theme::Theme;
+use crate::node::NodeDisplayState;
 use crate::Node;
 
 new_key_type! {pub struct NodeKey;}
 new_key_type! {pub struct ConnectorKey;}
 new_key_type! {pub struct InputPointKey;}
 new_key_type! {pub struct LinkKey;}
+new_key_type! {pub struct CommentKey;}
+
+/// Used to remember which graph was interacted with last,
+/// so that keyboard shortcuts only apply to one graph when graphs are nested
+const ACTIVE_GRAPH_ID: &str = "__NodeGraph active graph";
+
+/// Which edge of the selected nodes to line up with `NodeGraph::align_selection`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// The size assumed for nodes that haven't been shown yet, when laying out the graph
+const DEFAULT_NODE_SIZE: Vec2 = Vec2::new(150.0, 80.0);
+
+/// The smallest distance allowed between grid lines, in graph space
+const MIN_GRID_SPACING: f32 = 1.0;
+
+/// The furthest the graph can be zoomed out
+const MIN_ZOOM: f32 = 0.1;
+/// The furthest the graph can be zoomed in
+const MAX_ZOOM: f32 = 4.0;
+
+/// The size of the minimap in the corner of the graph
+const MINIMAP_SIZE: Vec2 = Vec2::new(200.0, 150.0);
+
+/// The height of the title bar of a comment frame, in graph space
+const COMMENT_TITLE_HEIGHT: f32 = 24.0;
+
+/// The colors comments can be given from their context menu
+const COMMENT_COLORS: [(&str, Color32); 6] = [
+    ("Gray", Color32::from_rgb(120, 120, 120)),
+    ("Blue", Color32::from_rgb(60, 110, 200)),
+    ("Green", Color32::from_rgb(60, 160, 80)),
+    ("Yellow", Color32::from_rgb(200, 170, 40)),
+    ("Red", Color32::from_rgb(200, 70, 60)),
+    ("Purple", Color32::from_rgb(140, 80, 190)),
+];
+
+/// Strips the module paths from a type name, so `alloc::vec::Vec<alloc::string::String>` becomes `Vec<String>`
+fn short_type_name(type_name: &str) -> String {
+    let mut short = String::new();
+    let mut segment_start = 0;
+    for (index, c) in type_name.char_indices() {
+        if !(c.is_alphanumeric() || c == '_' || c == ':') {
+            short.push_str(type_name[segment_start..index].rsplit("::").next().unwrap_or_default());
+            short.push(c);
+            segment_start = index + c.len_utf8();
+        }
+    }
+    short.push_str(type_name[segment_start..].rsplit("::").next().unwrap_or_default());
+    short
+}
+
+/// Describes a port to screen readers, like "Adder, input 2, u8, connected to Source"
+fn port_label(title: &str, input: bool, index: usize, type_name: &str, connected_to: &[&str]) -> String {
+    let direction = if input { "input" } else { "output" };
+    let connection = if connected_to.is_empty() {
+        "not connected".to_owned()
+    } else {
+        format!("connected to {}", connected_to.join(", "))
+    };
+    format!("{title}, {direction} {}, {}, {connection}", index + 1, short_type_name(type_name))
+}
+
+/// Moves a position to the nearest intersection of a grid with the given spacing
+fn snap_to_grid(position: Pos2, spacing: f32) -> Pos2 {
+    (position.to_vec2() / spacing).round().to_pos2() * spacing
+}
+
+/// The types of the inputs and outputs of a node, without showing it
+/// The body is taken from a clone, so the node's own state is left alone
+fn port_types(node: &dyn Node) -> (Vec<TypeId>, Vec<TypeId>) {
+    let mut node = dyn_clone::clone_box(node);
+    let (inputs, _, outputs) = node.body();
+    (
+        inputs.into_iter().map(|input| input.input_type).collect(),
+        outputs.into_iter().map(|output| output.output_type).collect(),
+    )
+}
 
 #[derive(Clone)]
 struct NodeInformation<'a> {
     node: Box<dyn Node + 'a>,
     position: Pos2,
+    /// The bounds of the node from the last time it was shown, in graph space
+    /// `None` until the node has been shown once
+    rect: Option<Rect>,
+    /// Port positions from the last time the node was shown, used while the node is culled
+    input_positions: Vec<Pos2>,
+    output_positions: Vec<Pos2>,
+    /// Port types from the last time the node was shown, used for linking with the keyboard
+    input_types: Vec<(TypeId, &'static str)>,
+    output_types: Vec<(TypeId, &'static str)>,
+    display_state: NodeDisplayState,
+}
+
+/// What keyboard navigation is on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum KeyboardFocus {
+    Node(NodeKey),
+    Port {
+        node_key: NodeKey,
+        input: bool,
+        index: usize,
+    },
+}
+
+#[derive(Clone)]
+struct RegisteredNode<'a> {
+    /// Has two uses: what to display in the node list, and a source for cloning nodes that don't need ids
+    node: Box<dyn Node + 'a>,
+    new_node_func: fn(Id, &Box<dyn Node + 'a>) -> Box<dyn Node + 'a>,
+    /// The header the node is listed under in the node list
+    category: String,
+    /// Extra words the node can be found by when searching
+    keywords: Vec<String>,
+}
+
+/// The popup for quickly adding a node by searching for it
+#[derive(Clone)]
+struct QuickAdd {
+    /// Where the new node will be placed, in graph space
+    position: Pos2,
+    search: String,
+    /// The port a link was dragged from before being dropped on empty canvas, if any
+    /// Only nodes that can connect to it are listed, and the chosen node is connected to it
+    link: Option<DroppedLink>,
+}
+
+/// A link dragged from a port and dropped without reaching another port
+#[derive(Clone, Copy)]
+struct DroppedLink {
+    node_key: NodeKey,
+    port_type: TypeId,
+    from_input: bool,
+    index: usize,
+}
+
+/// Text on the canvas, either as a colored frame behind nodes or a free floating note
+/// Comments are only annotation, and take no part in evaluation
+#[derive(Clone)]
+struct Comment {
+    text: String,
+    /// In graph space
+    rect: Rect,
+    color: Color32,
+    /// Frames show their text as a title, and carry the nodes and notes inside them along when dragged
+    frame: bool,
+}
+
+/// The power drawn and generated by the nodes of a graph, worked out while evaluating it
+#[derive(Clone, Default)]
+struct PowerSummary {
+    /// The nodes that draw or generate power, with what they draw and what they generate
+    nodes: Vec<(NodeKey, f64, f64)>,
+}
+
+impl PowerSummary {
+    fn draw(&self) -> f64 {
+        self.nodes.iter().map(|(_, draw, _)| draw).sum()
+    }
+
+    fn supply(&self) -> f64 {
+        self.nodes.iter().map(|(_, _, supply)| supply).sum()
+    }
+}
+
+/// An output making more than the inputs linked to it use
+#[derive(Clone)]
+struct Surplus {
+    output: (NodeKey, usize),
+    item: String,
+    /// The amount left over per minute
+    per_minute: f64,
+    /// Whether the rest goes to a node taking excess, rather than backing up the output
+    voided: bool,
+}
+
+/// What is left over from the outputs of a graph, worked out while evaluating it
+#[derive(Clone, Default)]
+struct SurplusSummary {
+    surpluses: Vec<Surplus>,
+    /// The inputs that need an item or fluid, which surplus can be recycled into
+    demands: Vec<((NodeKey, usize), ItemFlow)>,
+}
+
+impl SurplusSummary {
+    /// Works out the surplus of each output from what it makes and what the inputs linked to it need
+    /// Each link comes with the flow it carried when it was evaluated, if known, as pipes can carry less than is needed
+    fn new<'l>(
+        supplies: Vec<((NodeKey, usize), ItemFlow)>,
+        demands: Vec<((NodeKey, usize), Demand)>,
+        links: impl IntoIterator<Item = (&'l LinkInformation, Option<f64>)>,
+    ) -> Self {
+        let links: Vec<_> = links.into_iter().collect();
+        let demand_of = |input: (NodeKey, usize)| demands.iter().find(|(port, _)| *port == input).map(|(_, demand)| demand);
+        let mut surpluses = Vec::new();
+        for (output, supply) in supplies {
+            let linked: Vec<_> = links.iter().filter(|(link, _)| link.output == output).map(|(link, carried)| (demand_of(link.input), *carried)).collect();
+            // What happens to the rest is unknown when it goes into a node without a demand, like a tank
+            if linked.iter().any(|(demand, _)| demand.is_none()) {
+                continue;
+            }
+            let used: f64 = linked
+                .iter()
+                .map(|(demand, carried)| match demand {
+                    Some(Demand::Flow(flow)) if flow.item == supply.item => carried.map_or(flow.per_minute, |carried| carried.min(flow.per_minute)),
+                    _ => 0.0,
+                })
+                .sum();
+            let per_minute = supply.per_minute - used;
+            if per_minute > 1e-6 {
+                let voided = linked.iter().any(|(demand, _)| *demand == Some(&Demand::Excess));
+                surpluses.push(Surplus { output, item: supply.item, per_minute, voided });
+            }
+        }
+        let demands = demands
+            .into_iter()
+            .filter_map(|(input, demand)| match demand {
+                Demand::Flow(flow) => Some((input, flow)),
+                Demand::Excess => None,
+            })
+            .collect();
+        Self { surpluses, demands }
+    }
+}
+
+#[derive(Clone)]
+struct LinkInformation {
+    /// The node and index of the input end
+    input: (NodeKey, usize),
+    /// The node and index of the output end
+    output: (NodeKey, usize),
+    /// Points the link is routed through on its way from the output to the input, in graph space
+    reroutes: Vec<Pos2>,
+    /// How many pipes long the link is, which limits how much it carries when it carries fluid
+    pipe_length: u32,
+}
+
+impl LinkInformation {
+    fn new(input: (NodeKey, usize), output: (NodeKey, usize)) -> Self {
+        Self {
+            input,
+            output,
+            reroutes: Vec::new(),
+            pipe_length: DEFAULT_PIPE_LENGTH,
+        }
+    }
+}
+
+/// Adds a link, replacing any other link into the same input, as inputs only take one value
+/// Adding a link that already exists leaves it as it is
+fn insert_link(links: &mut SlotMap<LinkKey, LinkInformation>, link: LinkInformation) {
+    if links.values().any(|existing| existing.input == link.input && existing.output == link.output) {
+        return;
+    }
+    links.retain(|_, existing| existing.input != link.input);
+    links.insert(link);
+}
+
+/// A link segment leaving `from` and entering `to` horizontally, in screen space
+fn link_bezier(from: Pos2, to: Pos2, stroke: Stroke) -> CubicBezierShape {
+    let handle = Vec2::new(((to.x - from.x).abs() / 2.0).max(30.0), 0.0);
+    CubicBezierShape::from_points_stroke([from, from + handle, to - handle, to], false, Color32::TRANSPARENT, stroke)
+}
+
+/// The distance from `point` to the closest point on a line through `points`
+fn distance_to_line(points: &[Pos2], point: Pos2) -> f32 {
+    points
+        .windows(2)
+        .map(|segment| {
+            let (start, end) = (segment[0], segment[1]);
+            let length_sq = start.distance_sq(end);
+            let t = if length_sq > 0.0 {
+                ((point - start).dot(end - start) / length_sq).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            point.distance(start + (end - start) * t)
+        })
+        .fold(f32::INFINITY, f32::min)
 }
 
 #[derive(Clone)]
@@ -33,18 +353,62 @@ pub struct NodeGraph<'a, 'b> {
     nodes: SlotMap<NodeKey, NodeInformation<'b>>,
     // attachment_points: SlotMap<ConnectorKey, ConnectorInformation>,
     // input_points: SlotMap<InputPointKey, InputPointInformation>,
-    // links: SlotMap<LinkKey, LinkInformation>,
+    links: SlotMap<LinkKey, LinkInformation>,
+    comments: SlotMap<CommentKey, Comment>,
+    /// The comment whose text is being edited
+    editing_comment: Option<CommentKey>,
     transform: TSTransform,
     id: Id,
-    /// The boxed node of these tuples has two uses: what to display in the node list,
-    /// and a source for cloning nodes that don't need ids
-    registered_nodes: Vec<(Box<dyn Node + 'a>, fn(Id, &Box<dyn Node + 'a>) -> Box<dyn Node + 'a>)>,
+    registered_nodes: Vec<RegisteredNode<'a>>,
     display_list_id_source: usize,
     new_node_id_source: usize,
     pub selector_panel_enabled: bool,
+    /// Show a panel on the right for editing the selected node
+    pub inspector_panel_enabled: bool,
+    /// Show a panel at the bottom with the power drawn and generated by the graph
+    pub power_panel_enabled: bool,
+    power_summary: PowerSummary,
+    /// Show a panel at the bottom with the surplus of every output, and ways of dealing with it
+    pub surplus_panel_enabled: bool,
+    surplus_summary: SurplusSummary,
+    /// Links whose ports were gone or carried another type the last time the graph was evaluated
+    /// They are kept, as the change can be undone, such as by picking the old recipe again, but they carry nothing
+    broken_links: HashSet<LinkKey>,
+    /// Show a panel on the right with the bill of materials of the graph, see `report`
+    pub report_panel_enabled: bool,
+    /// Used by the report panel to work out the construction cost, which isn't shown without it
+    recipe_database: Option<Rc<RecipeDatabase>>,
+    pub construction_settings: ConstructionSettings,
+    /// Whether the project uses alternate recipes, shared with the nodes that pick recipes and saved with the graph
+    alternate_recipes: Rc<Cell<bool>>,
+    /// List registered nodes by title only, instead of showing them in full
+    pub compact_node_list: bool,
+    node_list_search: String,
+    quick_add: Option<QuickAdd>,
+    pub grid_enabled: bool,
+    /// Snap nodes to the grid while dragging them, even when the grid isn't shown
+    pub snap_to_grid: bool,
+    /// The distance between grid lines in graph space, changed through `set_grid_spacing`
+    grid_spacing: f32,
+    /// Show an overview of the whole graph in the bottom right corner
+    /// The minimap is drawn in screen space, so it is misplaced when the graph is inside a transformed layer, such as a `GraphNode`
+    pub minimap_enabled: bool,
+    pub theme: Theme,
+    /// Where the canvas was drawn last frame, in screen space
+    canvas_rect: Rect,
+    /// Named views of the graph, saved with it
+    bookmarks: Vec<(String, TSTransform)>,
+    /// The name being typed for a new bookmark in the context menu
+    new_bookmark_name: String,
     link_drag_info: Option<(NodeKey, TypeId, Pos2, bool, usize)>,
     next_frame_link_dropped: bool,
-    links: Vec<((NodeKey, usize), (NodeKey, usize))>,
+    pub shortcuts: ShortcutMap,
+    keyboard_focus: Option<KeyboardFocus>,
+    /// The port a link was started from with the keyboard, waiting for the port at the other end to be picked
+    keyboard_link_start: Option<KeyboardFocus>,
+    selection: HashSet<NodeKey>,
+    /// Where the current box selection started, in graph space
+    box_select_start: Option<Pos2>,
 }
 
 impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
@@ -53,15 +417,42 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
             id: id.into().with("__NodeGraph"),
             nodes: Default::default(),
             // attachment_points: Default::default(),
-            // links: Default::default(),
+            links: Default::default(),
+            comments: Default::default(),
+            editing_comment: Default::default(),
             transform: Default::default(),
             registered_nodes: Default::default(),
             selector_panel_enabled: Default::default(),
+            inspector_panel_enabled: Default::default(),
+            power_panel_enabled: Default::default(),
+            power_summary: Default::default(),
+            surplus_panel_enabled: Default::default(),
+            report_panel_enabled: Default::default(),
+            recipe_database: Default::default(),
+            construction_settings: Default::default(),
+            alternate_recipes: Default::default(),
+            surplus_summary: Default::default(),
+            broken_links: Default::default(),
+            compact_node_list: Default::default(),
+            node_list_search: Default::default(),
+            quick_add: Default::default(),
+            grid_enabled: true,
+            snap_to_grid: Default::default(),
+            grid_spacing: 20.0,
+            minimap_enabled: Default::default(),
+            theme: Default::default(),
+            canvas_rect: Rect::NOTHING,
+            bookmarks: Default::default(),
+            new_bookmark_name: Default::default(),
             link_drag_info: Default::default(),
             next_frame_link_dropped: Default::default(),
-            links: Default::default(),
+            shortcuts: Default::default(),
+            keyboard_focus: Default::default(),
+            keyboard_link_start: Default::default(),
             display_list_id_source: Default::default(),
             new_node_id_source: Default::default(),
+            selection: Default::default(),
+            box_select_start: Default::default(),
             // input_points: Default::default(),
         }
     }
@@ -69,24 +460,857 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
     /// Registers a node for spawning from the node selection list
     /// The node given is what will be rendered in the list
     /// and what will be placed when dragging in from the list
+    /// It is listed under `category`, and can be searched for by its title, category, or any of the `keywords`
     /// Note that registering a node multiple times will duplicate it in the display
-    pub fn register_node<'c>(&mut self, node: impl Node + 'c + 'a) {
-        self.registered_nodes.push((Box::new(node), |_, node| node.clone()));
+    pub fn register_node<'c>(&mut self, node: impl Node + 'c + 'a, category: &str, keywords: &[&str]) {
+        self.registered_nodes.push(RegisteredNode {
+            node: Box::new(node),
+            new_node_func: |_, node| node.clone(),
+            category: category.to_owned(),
+            keywords: keywords.iter().map(|keyword| keyword.to_string()).collect(),
+        });
     }
 
     /// Used as an alternate method of adding nodes to the graph 
     /// for nodes that require a unique Id to work
-    pub fn register_node_with_id<T>(&mut self) where T: CreatableNode<'a> {
-        self.registered_nodes.push((T::new_with_id(self.id.with("displayed node").with(self.display_list_id_source)), |id, _| T::new_with_id(id)));
+    pub fn register_node_with_id<T>(&mut self, category: &str, keywords: &[&str]) where T: CreatableNode<'a> {
+        self.registered_nodes.push(RegisteredNode {
+            node: T::new_with_id(self.id.with("displayed node").with(self.display_list_id_source)),
+            new_node_func: |id, _| T::new_with_id(id),
+            category: category.to_owned(),
+            keywords: keywords.iter().map(|keyword| keyword.to_string()).collect(),
+        });
         self.display_list_id_source += 1;
     }
 
+    /// Creates a new node from the registered node at `index`, giving it a fresh id
+    fn create_registered_node(&mut self, index: usize) -> Box<dyn Node + 'a> {
+        let registered = &self.registered_nodes[index];
+        let new_node = (registered.new_node_func)(self.id.with("new node").with(self.new_node_id_source), &registered.node);
+        self.new_node_id_source += 1;
+        new_node
+    }
+
+    /// The types of the inputs and outputs of the registered node at `index`
+    fn registered_node_ports(&self, index: usize) -> (Vec<TypeId>, Vec<TypeId>) {
+        let mut node = self.registered_nodes[index].node.clone();
+        let (inputs, _, outputs) = node.body();
+        (
+            inputs.into_iter().map(|input| input.input_type).collect(),
+            outputs.into_iter().map(|output| output.output_type).collect(),
+        )
+    }
+
+    /// The port of the registered node at `index` that `link` can connect to, if it has one
+    fn compatible_port(&self, index: usize, link: DroppedLink) -> Option<usize> {
+        let (inputs, outputs) = self.registered_node_ports(index);
+        let ports = if link.from_input { outputs } else { inputs };
+        ports.iter().position(|port_type| *port_type == link.port_type)
+    }
+
+    /// The indices of registered nodes matching `query`, best match first
+    fn search_registered_nodes(&self, query: &str) -> Vec<usize> {
+        let mut matches: Vec<_> = self
+            .registered_nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, registered)| {
+                let score = palette::node_score(query, registered.node.title(), &registered.category, &registered.keywords)?;
+                Some((index, score))
+            })
+            .collect();
+        matches.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+        matches.into_iter().map(|(index, _)| index).collect()
+    }
+
+    /// Shows a registered node in the node list, returning where it was dropped if it was dragged out of the list
+    fn show_node_list_entry(&self, ui: &mut Ui, index: usize) -> Option<Pos2> {
+        let mut node = self.registered_nodes[index].node.clone();
+        let response = if self.compact_node_list {
+            ui.add(egui::Label::new(node.title().to_owned()).selectable(false).sense(Sense::drag()))
+        } else {
+            let rect = ui.add_enabled_ui(true, |ui| node.show(ui, &mut NodeDisplayState::default(), &self.theme)).response.rect;
+            ui.allocate_rect(rect, Sense::drag())
+        };
+        if response.dragged() {
+            egui::Area::new(self.id.with("drag display").with(index))
+                .fixed_pos(
+                    ui.ctx()
+                        .input(|i| i.pointer.hover_pos())
+                        .unwrap_or_default(),
+                )
+                .show(ui.ctx(), |ui| {
+                    ui.add_enabled_ui(true, |ui| node.show(ui, &mut NodeDisplayState::default(), &self.theme));
+                });
+        }
+        if response.drag_stopped() {
+            ui.ctx().input(|i| i.pointer.interact_pos())
+        } else {
+            None
+        }
+    }
+
+    /// Shows the quick add popup if it is open, adding the chosen node where the popup was opened
+    fn show_quick_add(&mut self, ctx: &Context) {
+        let Some(mut quick_add) = self.quick_add.take() else {
+            return;
+        };
+        let mut chosen = None;
+        let mut close = ctx.input(|i| i.key_pressed(Key::Escape));
+        let response = egui::Area::new(self.id.with("quick add"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(self.canvas_transform() * quick_add.position)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(200.0);
+                    let hint_text = if quick_add.link.is_some() { "Add connected node" } else { "Add node" };
+                    let search = ui.add(egui::TextEdit::singleline(&mut quick_add.search).hint_text(hint_text));
+                    search.request_focus();
+                    let mut results = self.search_registered_nodes(&quick_add.search);
+                    if let Some(link) = quick_add.link {
+                        results.retain(|index| self.compatible_port(*index, link).is_some());
+                    }
+                    if results.is_empty() {
+                        ui.weak("No matching nodes");
+                    }
+                    if search.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                        chosen = results.first().copied();
+                    }
+                    for index in results.into_iter().take(10) {
+                        let registered = &self.registered_nodes[index];
+                        ui.horizontal(|ui| {
+                            if ui.selectable_label(false, registered.node.title()).clicked() {
+                                chosen = Some(index);
+                            }
+                            ui.weak(&registered.category);
+                        });
+                    }
+                });
+            })
+            .response;
+        if let Some(index) = chosen {
+            let node = self.create_registered_node(index);
+            let node_key = self.add_node(node, quick_add.position);
+            if let Some(link) = quick_add.link {
+                if let Some(port) = self.compatible_port(index, link) {
+                    if link.from_input {
+                        insert_link(&mut self.links, LinkInformation::new((link.node_key, link.index), (node_key, port)));
+                    } else {
+                        insert_link(&mut self.links, LinkInformation::new((node_key, port), (link.node_key, link.index)));
+                    }
+                }
+            }
+            self.selection = HashSet::from([node_key]);
+            close = true;
+        }
+        if !close && !response.clicked_elsewhere() {
+            self.quick_add = Some(quick_add);
+        }
+    }
+
     /// Adds a node to the graph, returning the `NodeKey` unique to it
     /// Note that if you have multiple graphs, using a `NodeKey` from one graph on a different graph is an error
     /// The result is safe, but unspecified, and cannot be detected at runtime
     /// Due to some usages involving creating unspecified numbers of graphs, and for ease of implementation, it is not possible to use a custom key type
     pub fn add_node<'c: 'b>(&mut self, node: Box<dyn Node + 'c>, position: Pos2) -> NodeKey {
-        self.nodes.insert(NodeInformation { node, position })
+        self.nodes.insert(NodeInformation {
+            node,
+            position,
+            rect: None,
+            input_positions: Vec::new(),
+            output_positions: Vec::new(),
+            input_types: Vec::new(),
+            output_types: Vec::new(),
+            display_state: Default::default(),
+        })
+    }
+
+    /// Removes a node and all links connected to it from the graph
+    /// Returns false if the node was not in the graph
+    pub fn remove_node(&mut self, node_key: NodeKey) -> bool {
+        self.selection.remove(&node_key);
+        self.links.retain(|_, link| link.input.0 != node_key && link.output.0 != node_key);
+        self.nodes.remove(node_key).is_some()
+    }
+
+    /// The keys of all currently selected nodes, in no particular order
+    pub fn selection(&self) -> impl Iterator<Item = NodeKey> + '_ {
+        self.selection.iter().copied()
+    }
+
+    pub fn is_selected(&self, node_key: NodeKey) -> bool {
+        self.selection.contains(&node_key)
+    }
+
+    /// Adds a node to the selection
+    /// Keys not in the graph are ignored
+    pub fn select(&mut self, node_key: NodeKey) {
+        if self.nodes.contains_key(node_key) {
+            self.selection.insert(node_key);
+        }
+    }
+
+    pub fn deselect(&mut self, node_key: NodeKey) {
+        self.selection.remove(&node_key);
+    }
+
+    pub fn select_all(&mut self) {
+        self.selection = self.nodes.keys().collect();
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+    }
+
+    /// Where a port of a node was last drawn, in graph space, following the node if it has moved since
+    fn port_position(&self, node_key: NodeKey, index: usize, input: bool) -> Pos2 {
+        let node_information = &self.nodes[node_key];
+        let node_rect = self.node_rect(node_key);
+        let offset = node_rect.min - node_information.rect.unwrap_or(node_rect).min;
+        let positions = if input { &node_information.input_positions } else { &node_information.output_positions };
+        positions.get(index).map_or(node_rect.center(), |pos| *pos + offset)
+    }
+
+    /// Moves keyboard focus to the next node or port, going through each node followed by its inputs and outputs
+    /// Focusing a node selects it
+    fn cycle_keyboard_focus(&mut self, backwards: bool) {
+        let order: Vec<_> = self
+            .nodes
+            .iter()
+            .flat_map(|(node_key, node_information)| {
+                let inputs = (0..node_information.input_positions.len()).map(move |index| KeyboardFocus::Port { node_key, input: true, index });
+                let outputs = (0..node_information.output_positions.len()).map(move |index| KeyboardFocus::Port { node_key, input: false, index });
+                std::iter::once(KeyboardFocus::Node(node_key)).chain(inputs).chain(outputs)
+            })
+            .collect();
+        if order.is_empty() {
+            self.keyboard_focus = None;
+            return;
+        }
+        let current = self.keyboard_focus.and_then(|focus| order.iter().position(|x| *x == focus));
+        let next = match (current, backwards) {
+            (Some(index), false) => (index + 1) % order.len(),
+            (Some(index), true) => (index + order.len() - 1) % order.len(),
+            (None, false) => 0,
+            (None, true) => order.len() - 1,
+        };
+        self.keyboard_focus = Some(order[next]);
+        if let KeyboardFocus::Node(node_key) = order[next] {
+            self.selection = HashSet::from([node_key]);
+        }
+    }
+
+    /// Starts a link from the focused port, or links it to the port a link was already started from
+    fn start_keyboard_link(&mut self) {
+        let Some(focus @ KeyboardFocus::Port { .. }) = self.keyboard_focus else {
+            return;
+        };
+        match self.keyboard_link_start.take() {
+            Some(start) => self.link_ports(start, focus),
+            None => self.keyboard_link_start = Some(focus),
+        }
+    }
+
+    /// Links two ports if one is an input and the other an output of the same type
+    /// Replaces the link already going into the input, if there is one
+    fn link_ports(&mut self, a: KeyboardFocus, b: KeyboardFocus) {
+        let (
+            KeyboardFocus::Port { node_key: a_key, input: a_input, index: a_index },
+            KeyboardFocus::Port { node_key: b_key, input: b_input, index: b_index },
+        ) = (a, b)
+        else {
+            return;
+        };
+        let ((input_key, input_index), (output_key, output_index)) = match (a_input, b_input) {
+            (true, false) => ((a_key, a_index), (b_key, b_index)),
+            (false, true) => ((b_key, b_index), (a_key, a_index)),
+            _ => return,
+        };
+        let input_type = self.nodes.get(input_key).and_then(|node| node.input_types.get(input_index)).map(|(t, _)| t);
+        let output_type = self.nodes.get(output_key).and_then(|node| node.output_types.get(output_index)).map(|(t, _)| t);
+        if input_type.is_some() && input_type == output_type {
+            insert_link(&mut self.links, LinkInformation::new((input_key, input_index), (output_key, output_index)));
+        }
+    }
+
+    /// Paints a ring around the focused node or port, and a line from the port a keyboard link was started from
+    fn paint_keyboard_focus(&self, ctx: &Context, transform: TSTransform, clip_rect: Rect) {
+        let painter = ctx
+            .layer_painter(LayerId::new(egui::Order::Foreground, self.id.with("keyboard focus painter layer")))
+            .with_clip_rect(clip_rect);
+        let focus_stroke = Stroke::new(2.0, ctx.style().visuals.strong_text_color());
+        let port_position = |focus: KeyboardFocus| match focus {
+            KeyboardFocus::Port { node_key, input, index } if self.nodes.contains_key(node_key) => {
+                Some(transform * self.port_position(node_key, index, input))
+            }
+            _ => None,
+        };
+        match self.keyboard_focus {
+            Some(KeyboardFocus::Node(node_key)) if self.nodes.contains_key(node_key) => {
+                painter.rect_stroke(transform * self.node_rect(node_key).expand(6.0), 4.0, focus_stroke);
+            }
+            Some(focus) => {
+                if let Some(position) = port_position(focus) {
+                    painter.circle_stroke(position, 9.0 * transform.scaling, focus_stroke);
+                }
+            }
+            None => {}
+        }
+        if let Some(start) = self.keyboard_link_start.and_then(port_position) {
+            painter.circle_stroke(start, 9.0 * transform.scaling, Stroke::new(2.0, self.theme.link_color));
+            if let Some(end) = self.keyboard_focus.and_then(port_position) {
+                painter.line_segment([start, end], Stroke::new(2.0, self.theme.link_color));
+            }
+        }
+    }
+
+    /// Describes a link to screen readers, like "Link from Source output 1 to Adder input 2, u8"
+    fn link_label(&self, link: &LinkInformation) -> String {
+        let title = |node_key: NodeKey| self.nodes.get(node_key).map_or("missing node", |node| node.node.title());
+        let type_name = self
+            .nodes
+            .get(link.input.0)
+            .and_then(|node| node.input_types.get(link.input.1))
+            .map_or("unknown".to_owned(), |(_, type_name)| short_type_name(type_name));
+        format!(
+            "Link from {} output {} to {} input {}, {type_name}",
+            title(link.output.0),
+            link.output.1 + 1,
+            title(link.input.0),
+            link.input.1 + 1,
+        )
+    }
+
+    /// Moves every selected node by `delta`, in graph space
+    pub fn move_selection(&mut self, delta: Vec2) {
+        for node_key in self.selection.iter() {
+            self.nodes[*node_key].position += delta;
+        }
+    }
+
+    /// Removes every selected node from the graph
+    pub fn delete_selection(&mut self) {
+        for node_key in std::mem::take(&mut self.selection) {
+            self.remove_node(node_key);
+        }
+    }
+
+    /// Clones every selected node, along with the links between them
+    /// The clones are placed slightly offset from the originals and become the new selection
+    pub fn duplicate_selection(&mut self) {
+        let mut new_keys = HashMap::new();
+        for node_key in self.selection.iter() {
+            let node_information = &self.nodes[*node_key];
+            let new_key = self.nodes.insert(NodeInformation {
+                position: node_information.position + Vec2::new(20.0, 20.0),
+                rect: None,
+                ..node_information.clone()
+            });
+            new_keys.insert(*node_key, new_key);
+        }
+        let new_links: Vec<_> = self
+            .links
+            .values()
+            .filter_map(|link| {
+                Some(LinkInformation {
+                    input: (*new_keys.get(&link.input.0)?, link.input.1),
+                    output: (*new_keys.get(&link.output.0)?, link.output.1),
+                    reroutes: link.reroutes.iter().map(|pos| *pos + Vec2::new(20.0, 20.0)).collect(),
+                    pipe_length: link.pipe_length,
+                })
+            })
+            .collect();
+        for link in new_links {
+            self.links.insert(link);
+        }
+        self.selection = new_keys.into_values().collect();
+    }
+
+    /// The bounds of a node at its current position, in graph space
+    /// Nodes that have not been shown yet have no size
+    fn node_rect(&self, node_key: NodeKey) -> Rect {
+        let node_information = &self.nodes[node_key];
+        Rect::from_min_size(
+            node_information.position,
+            node_information.rect.map_or(Vec2::ZERO, |rect| rect.size()),
+        )
+    }
+
+    /// Lines up an edge of every selected node with the outermost one
+    /// Nodes that have not been shown yet are aligned by their position
+    pub fn align_selection(&mut self, alignment: Alignment) {
+        let rects: Vec<_> = self
+            .selection
+            .iter()
+            .map(|node_key| (*node_key, self.node_rect(*node_key)))
+            .collect();
+        let target = match alignment {
+            Alignment::Left => rects.iter().map(|(_, rect)| rect.left()).reduce(f32::min),
+            Alignment::Right => rects.iter().map(|(_, rect)| rect.right()).reduce(f32::max),
+            Alignment::Top => rects.iter().map(|(_, rect)| rect.top()).reduce(f32::min),
+            Alignment::Bottom => rects.iter().map(|(_, rect)| rect.bottom()).reduce(f32::max),
+        };
+        let Some(target) = target else { return };
+        for (node_key, rect) in rects {
+            self.nodes[node_key].position += match alignment {
+                Alignment::Left => Vec2::new(target - rect.left(), 0.0),
+                Alignment::Right => Vec2::new(target - rect.right(), 0.0),
+                Alignment::Top => Vec2::new(0.0, target - rect.top()),
+                Alignment::Bottom => Vec2::new(0.0, target - rect.bottom()),
+            };
+        }
+    }
+
+    /// Spreads the selected nodes out so the gaps between them are equal
+    /// The outermost nodes stay where they are
+    pub fn distribute_selection_horizontally(&mut self) {
+        self.distribute_selection(0);
+    }
+
+    /// Spreads the selected nodes out so the gaps between them are equal
+    /// The outermost nodes stay where they are
+    pub fn distribute_selection_vertically(&mut self) {
+        self.distribute_selection(1);
+    }
+
+    /// `axis` is 0 for horizontal and 1 for vertical, to index into positions and sizes
+    fn distribute_selection(&mut self, axis: usize) {
+        let mut rects: Vec<_> = self
+            .selection
+            .iter()
+            .map(|node_key| (*node_key, self.node_rect(*node_key)))
+            .collect();
+        if rects.len() < 3 {
+            return;
+        }
+        rects.sort_by(|(_, a), (_, b)| a.center()[axis].total_cmp(&b.center()[axis]));
+        let start = rects.iter().map(|(_, rect)| rect.min[axis]).reduce(f32::min).unwrap();
+        let end = rects.iter().map(|(_, rect)| rect.max[axis]).reduce(f32::max).unwrap();
+        let total_size: f32 = rects.iter().map(|(_, rect)| rect.size()[axis]).sum();
+        let gap = (end - start - total_size) / (rects.len() - 1) as f32;
+        let mut next = start;
+        for (node_key, rect) in rects {
+            self.nodes[node_key].position[axis] += next - rect.min[axis];
+            next += rect.size()[axis] + gap;
+        }
+    }
+
+    /// Arranges nodes in layers from left to right, following links from outputs to inputs,
+    /// while keeping crossing links to a minimum
+    /// With `selection_only` only the selected nodes are moved, keeping the top left corner of the selection in place
+    /// Doesn't need the graph to be shown, nodes that haven't been shown yet are given a default size
+    pub fn auto_layout(&mut self, selection_only: bool) {
+        let mut node_keys: Vec<NodeKey> = if selection_only {
+            self.selection.iter().copied().collect()
+        } else {
+            self.nodes.keys().collect()
+        };
+        // Sorting keeps the layout stable, and lets nodes keep their vertical order where it doesn't matter
+        node_keys.sort_by(|a, b| {
+            let (a, b) = (self.nodes[*a].position, self.nodes[*b].position);
+            a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+        });
+        let Some(top_left) = node_keys.iter().map(|node_key| self.nodes[*node_key].position).reduce(|a, b| a.min(b)) else {
+            return;
+        };
+        let sizes: Vec<_> = node_keys
+            .iter()
+            .map(|node_key| self.nodes[*node_key].rect.map_or(DEFAULT_NODE_SIZE, |rect| rect.size()))
+            .collect();
+        let index_of = |node_key: &NodeKey| node_keys.iter().position(|x| x == node_key);
+        let edges: Vec<_> = self
+            .links
+            .values()
+            .filter_map(|link| Some((index_of(&link.output.0)?, index_of(&link.input.0)?)))
+            .collect();
+        let positions = layout::layered_layout(&sizes, &edges, Vec2::new(60.0, 30.0));
+        for (node_key, position) in node_keys.into_iter().zip(positions) {
+            self.nodes[node_key].position = top_left + position.to_vec2();
+        }
+    }
+
+    /// Serializes the whole graph, to be restored with `load`
+    pub fn save(&self) -> String {
+        let serialized = SerializedGraph {
+            nodes: self.serialized_nodes(self.nodes.keys()),
+            bookmarks: self
+                .bookmarks
+                .iter()
+                .map(|(name, transform)| SerializedBookmark {
+                    name: name.clone(),
+                    translation: transform.translation.into(),
+                    scaling: transform.scaling,
+                })
+                .collect(),
+            comments: self
+                .comments
+                .values()
+                .map(|comment| SerializedComment {
+                    text: comment.text.clone(),
+                    position: comment.rect.min.into(),
+                    size: comment.rect.size().into(),
+                    color: comment.color.to_array(),
+                    frame: comment.frame,
+                })
+                .collect(),
+            alternate_recipes: self.alternate_recipes.get(),
+        };
+        serde_json::to_string(&serialized).expect("serializing a graph can't fail")
+    }
+
+    /// Replaces the contents of the graph with a graph serialized by `save`
+    /// Nodes of a type that isn't registered with this graph are skipped, along with their links
+    /// Links to ports the loaded nodes no longer have, like after the recipe database changed, are dropped
+    pub fn load(&mut self, text: &str) -> Result<(), serde_json::Error> {
+        let serialized: SerializedGraph = serde_json::from_str(text)?;
+        self.nodes.clear();
+        self.links.clear();
+        self.selection.clear();
+        self.add_serialized_nodes(serialized.nodes, Vec2::ZERO);
+        self.bookmarks = serialized
+            .bookmarks
+            .into_iter()
+            .map(|bookmark| {
+                // A zoom of 0 or less in the file would break the view, so it is kept to the zooms the graph allows
+                let transform = TSTransform::new(bookmark.translation.into(), bookmark.scaling.clamp(MIN_ZOOM, MAX_ZOOM));
+                (bookmark.name, transform)
+            })
+            .collect();
+        self.alternate_recipes.set(serialized.alternate_recipes);
+        self.comments.clear();
+        self.editing_comment = None;
+        for comment in serialized.comments {
+            let [r, g, b, a] = comment.color;
+            self.comments.insert(Comment {
+                text: comment.text,
+                rect: Rect::from_min_size(comment.position.into(), comment.size.into()),
+                color: Color32::from_rgba_premultiplied(r, g, b, a),
+                frame: comment.frame,
+            });
+        }
+        Ok(())
+    }
+
+    /// Adds a comment frame covering `rect`, in graph space
+    pub fn add_comment_frame(&mut self, rect: Rect, text: impl Into<String>) -> CommentKey {
+        self.comments.insert(Comment {
+            text: text.into(),
+            rect,
+            color: COMMENT_COLORS[0].1,
+            frame: true,
+        })
+    }
+
+    /// Adds a note with its top left corner at `position`, in graph space
+    pub fn add_note(&mut self, position: Pos2, text: impl Into<String>) -> CommentKey {
+        self.comments.insert(Comment {
+            text: text.into(),
+            rect: Rect::from_min_size(position, Vec2::new(160.0, 80.0)),
+            color: COMMENT_COLORS[3].1,
+            frame: false,
+        })
+    }
+
+    pub fn remove_comment(&mut self, comment_key: CommentKey) -> bool {
+        self.comments.remove(comment_key).is_some()
+    }
+
+    /// Adds a comment frame around the selected nodes, or in the middle of the view if nothing is selected
+    fn add_comment_frame_around_selection(&mut self) {
+        let rect = self
+            .selection
+            .iter()
+            .map(|node_key| self.node_rect(*node_key))
+            .reduce(|a, b| a.union(b))
+            .map(|bounds| {
+                let bounds = bounds.expand(20.0);
+                Rect::from_min_max(bounds.min - Vec2::new(0.0, COMMENT_TITLE_HEIGHT), bounds.max)
+            })
+            .unwrap_or_else(|| Rect::from_center_size(self.view_center(), Vec2::new(300.0, 200.0)));
+        let comment_key = self.add_comment_frame(rect, "Comment");
+        self.editing_comment = Some(comment_key);
+    }
+
+    /// The middle of the canvas, in graph space
+    fn view_center(&self) -> Pos2 {
+        self.transform.inverse() * (self.canvas_rect.size() / 2.0).to_pos2()
+    }
+
+    /// Paints the comment frames and notes, and handles moving, resizing and editing them
+    /// This is done before the nodes are shown, so comments end up behind them
+    fn show_comments(&mut self, ui: &mut Ui, transform: TSTransform) {
+        let mut comment_keys: Vec<_> = self.comments.keys().collect();
+        // Frames go first, so notes on top of them can still be grabbed
+        comment_keys.sort_by_key(|comment_key| !self.comments[*comment_key].frame);
+        let mut removed_comment = None;
+        for comment_key in comment_keys {
+            let id = self.id.with("comment").with(comment_key);
+            let comment = &mut self.comments[comment_key];
+            let screen_rect = transform * comment.rect;
+            let title_rect = if comment.frame {
+                Rect::from_min_size(screen_rect.min, Vec2::new(screen_rect.width(), COMMENT_TITLE_HEIGHT * transform.scaling))
+            } else {
+                screen_rect
+            };
+            if comment.frame {
+                ui.painter().rect(screen_rect, 4.0, comment.color.gamma_multiply(0.15), Stroke::new(1.0, comment.color));
+                ui.painter().rect_filled(title_rect, 4.0, comment.color.gamma_multiply(0.6));
+            } else {
+                ui.painter().rect_filled(screen_rect, 4.0, comment.color.gamma_multiply(0.6));
+            }
+
+            let text_rect = title_rect.shrink(4.0 * transform.scaling);
+            let font = FontId::proportional(14.0 * transform.scaling);
+            let mut delta = Vec2::ZERO;
+            let mut activated = false;
+            if self.editing_comment == Some(comment_key) {
+                let text_edit = if comment.frame {
+                    egui::TextEdit::singleline(&mut comment.text)
+                } else {
+                    egui::TextEdit::multiline(&mut comment.text)
+                };
+                let response = ui.put(text_rect, text_edit.id(id.with("text")).font(font).frame(false));
+                if response.lost_focus() || response.clicked_elsewhere() {
+                    self.editing_comment = None;
+                } else {
+                    response.request_focus();
+                }
+            } else {
+                let text_color = ui.visuals().strong_text_color();
+                let galley = ui.painter().layout(comment.text.clone(), font, text_color, text_rect.width());
+                ui.painter().with_clip_rect(text_rect).galley(text_rect.min, galley, text_color);
+                let response = ui.interact(title_rect, id, Sense::click_and_drag());
+                activated = response.drag_started() || response.clicked();
+                delta = response.drag_delta() / transform.scaling;
+                if response.double_clicked() {
+                    self.editing_comment = Some(comment_key);
+                }
+                response.context_menu(|ui| {
+                    if ui.button("Edit text").clicked() {
+                        self.editing_comment = Some(comment_key);
+                        ui.close_menu();
+                    }
+                    ui.menu_button("Color", |ui| {
+                        for (name, color) in COMMENT_COLORS {
+                            if ui.button(name).clicked() {
+                                comment.color = color;
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    if ui.button("Delete").clicked() {
+                        removed_comment = Some(comment_key);
+                        ui.close_menu();
+                    }
+                });
+            }
+
+            let handle_rect = Rect::from_min_max(screen_rect.max - Vec2::splat(12.0), screen_rect.max);
+            let handle_response = ui.interact(handle_rect, id.with("resize"), Sense::drag());
+            for offset in [4.0, 8.0] {
+                ui.painter().line_segment(
+                    [handle_rect.right_top() + Vec2::new(-2.0, offset), handle_rect.left_bottom() + Vec2::new(offset, -2.0)],
+                    Stroke::new(1.0, ui.visuals().strong_text_color()),
+                );
+            }
+            let min_size = if comment.frame { Vec2::new(80.0, COMMENT_TITLE_HEIGHT * 2.0) } else { Vec2::new(40.0, 24.0) };
+            comment.rect.max = (comment.rect.max + handle_response.drag_delta() / transform.scaling).max(comment.rect.min + min_size);
+
+            if delta != Vec2::ZERO {
+                let rect = comment.rect;
+                if comment.frame {
+                    for node_information in self.nodes.values_mut() {
+                        let size = node_information.rect.map_or(DEFAULT_NODE_SIZE, |rect| rect.size());
+                        if rect.contains_rect(Rect::from_min_size(node_information.position, size)) {
+                            node_information.position += delta;
+                        }
+                    }
+                    for note in self.comments.values_mut().filter(|note| !note.frame && rect.contains_rect(note.rect)) {
+                        note.rect = note.rect.translate(delta);
+                    }
+                }
+                self.comments[comment_key].rect = rect.translate(delta);
+            }
+            if activated {
+                self.make_active(ui.ctx());
+            }
+        }
+        if let Some(comment_key) = removed_comment {
+            self.remove_comment(comment_key);
+        }
+    }
+
+    /// Zooms and pans so `rect`, in graph space, fills the canvas
+    /// Does nothing before the graph has been shown, as the size of the canvas isn't known yet
+    pub fn frame_rect(&mut self, rect: Rect) {
+        if !self.canvas_rect.is_positive() || !rect.is_finite() {
+            return;
+        }
+        let rect = rect.expand(40.0);
+        let scaling = (self.canvas_rect.width() / rect.width())
+            .min(self.canvas_rect.height() / rect.height())
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+        let canvas_center = self.canvas_rect.size() / 2.0;
+        self.transform = TSTransform::new(canvas_center - rect.center().to_vec2() * scaling, scaling);
+    }
+
+    /// Zooms and pans so every node is visible
+    pub fn frame_all(&mut self) {
+        if let Some(bounds) = self.nodes.keys().map(|node_key| self.node_rect(node_key)).reduce(|a, b| a.union(b)) {
+            self.frame_rect(bounds);
+        }
+    }
+
+    /// Zooms and pans so every selected node is visible
+    pub fn frame_selection(&mut self) {
+        if let Some(bounds) = self.selection.iter().map(|node_key| self.node_rect(*node_key)).reduce(|a, b| a.union(b)) {
+            self.frame_rect(bounds);
+        }
+    }
+
+    /// Remembers the current view under `name`, replacing any bookmark with the same name
+    /// Bookmarks are saved with the graph
+    pub fn add_bookmark(&mut self, name: impl Into<String>) {
+        let name = name.into();
+        self.remove_bookmark(&name);
+        self.bookmarks.push((name, self.transform));
+    }
+
+    pub fn remove_bookmark(&mut self, name: &str) {
+        self.bookmarks.retain(|(bookmark, _)| bookmark != name);
+    }
+
+    /// Moves the view to a bookmark, returning false if there is no bookmark with that name
+    pub fn go_to_bookmark(&mut self, name: &str) -> bool {
+        if let Some((_, transform)) = self.bookmarks.iter().find(|(bookmark, _)| bookmark == name) {
+            self.transform = TSTransform::new(transform.translation, transform.scaling.clamp(MIN_ZOOM, MAX_ZOOM));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The names of all bookmarks, in the order they were added
+    pub fn bookmarks(&self) -> impl Iterator<Item = &str> {
+        self.bookmarks.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Serializes the given nodes and the links between them, for use with `paste`
+    /// Links to nodes outside of `node_keys` are left out
+    pub fn serialize_nodes(&self, node_keys: impl IntoIterator<Item = NodeKey>) -> String {
+        serde_json::to_string(&self.serialized_nodes(node_keys)).expect("serializing nodes can't fail")
+    }
+
+    fn serialized_nodes(&self, node_keys: impl IntoIterator<Item = NodeKey>) -> SerializedNodes {
+        let node_keys: Vec<_> = node_keys.into_iter().filter(|node_key| self.nodes.contains_key(*node_key)).collect();
+        let index_of = |node_key: &NodeKey| node_keys.iter().position(|x| x == node_key);
+        SerializedNodes {
+            nodes: node_keys
+                .iter()
+                .map(|node_key| {
+                    let node_information = &self.nodes[*node_key];
+                    SerializedNode {
+                        type_name: node_information.node.type_name().to_owned(),
+                        position: node_information.position.into(),
+                        data: node_information.node.save(),
+                        collapsed: node_information.display_state.collapsed,
+                        body_size: node_information.display_state.body_size.map(Into::into),
+                    }
+                })
+                .collect(),
+            links: self
+                .links
+                .values()
+                .filter_map(|link| {
+                    Some(SerializedLink {
+                        input: (index_of(&link.input.0)?, link.input.1),
+                        output: (index_of(&link.output.0)?, link.output.1),
+                        reroutes: link.reroutes.iter().map(|pos| (*pos).into()).collect(),
+                        pipe_length: link.pipe_length,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Serializes the selected nodes, for putting on the clipboard
+    pub fn copy_selection(&self) -> String {
+        self.serialize_nodes(self.selection.iter().copied())
+    }
+
+    /// Adds nodes serialized by `serialize_nodes`, moved so the top left of the group is at `position`
+    /// The new nodes get fresh keys and become the selection
+    /// Nodes of a type that isn't registered with this graph are skipped, along with their links
+    pub fn paste(&mut self, text: &str, position: Pos2) -> Result<(), serde_json::Error> {
+        let serialized: SerializedNodes = serde_json::from_str(text)?;
+        let top_left = serialized
+            .nodes
+            .iter()
+            .map(|node| Pos2::from(node.position))
+            .reduce(|a, b| a.min(b))
+            .unwrap_or(position);
+        self.selection = self.add_serialized_nodes(serialized, position - top_left).into_iter().collect();
+        Ok(())
+    }
+
+    /// Whether both ends of a link are ports of the same type, going into an input that isn't linked yet
+    /// The port types are taken from the bodies of the nodes, so they don't have to have been shown
+    fn link_fits(&self, link: &LinkInformation) -> bool {
+        let port_type = |(node_key, index): (NodeKey, usize), is_input: bool| {
+            let (inputs, outputs) = port_types(&*self.nodes.get(node_key)?.node);
+            let types = if is_input { inputs } else { outputs };
+            types.get(index).copied()
+        };
+        let input_type = port_type(link.input, true);
+        input_type.is_some() && input_type == port_type(link.output, false) && !self.links.values().any(|other| other.input == link.input)
+    }
+
+    /// Returns the keys of the nodes that could be created
+    /// Links that don't fit the created nodes are dropped
+    fn add_serialized_nodes(&mut self, serialized: SerializedNodes, offset: Vec2) -> Vec<NodeKey> {
+        let mut new_keys = Vec::new();
+        for serialized_node in serialized.nodes {
+            let index = self
+                .registered_nodes
+                .iter()
+                .position(|registered| registered.node.type_name() == serialized_node.type_name);
+            new_keys.push(index.map(|index| {
+                let mut node = self.create_registered_node(index);
+                node.load(&serialized_node.data);
+                let node_key = self.add_node(node, Pos2::from(serialized_node.position) + offset);
+                self.nodes[node_key].display_state = NodeDisplayState {
+                    collapsed: serialized_node.collapsed,
+                    body_size: serialized_node.body_size.map(Into::into),
+                };
+                node_key
+            }));
+        }
+        for link in serialized.links {
+            if let (Some(Some(input_key)), Some(Some(output_key))) = (new_keys.get(link.input.0), new_keys.get(link.output.0)) {
+                let link = LinkInformation {
+                    input: (*input_key, link.input.1),
+                    output: (*output_key, link.output.1),
+                    reroutes: link.reroutes.into_iter().map(|pos| Pos2::from(pos) + offset).collect(),
+                    pipe_length: link.pipe_length.clamp(1, MAX_PIPE_LENGTH),
+                };
+                // Hand edited or outdated text can have links to ports that don't exist or don't match,
+                // or more than one link into an input, none of which can be evaluated
+                if self.link_fits(&link) {
+                    self.links.insert(link);
+                }
+            }
+        }
+        new_keys.into_iter().flatten().collect()
+    }
+
+    /// Marks this graph as the one keyboard shortcuts apply to
+    fn make_active(&self, ctx: &Context) {
+        ctx.data_mut(|data| data.insert_temp(Id::new(ACTIVE_GRAPH_ID), self.id));
+    }
+
+    /// If this graph was the last one interacted with, and no text is being edited
+    fn is_active(&self, ctx: &Context) -> bool {
+        !ctx.wants_keyboard_input() && ctx.data(|data| data.get_temp(Id::new(ACTIVE_GRAPH_ID))) == Some(self.id)
     }
 
     /// Show the graph using a context
@@ -103,48 +1327,308 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
         if self.selector_panel_enabled {
             let mut node_to_add = None;
             egui::SidePanel::left(self.id.with("node list")).show_inside(ui, |ui| {
-                for (index, (mut node, new_node_func)) in self.registered_nodes.clone().into_iter().enumerate() {
-                    let rect = ui.add_enabled_ui(true, |ui| node.show(ui)).response.rect;
-                    let response = ui.allocate_rect(rect, Sense::drag());
-                    if response.dragged() {
-                        egui::Area::new(self.id.with("drag display").with(index))
-                            .fixed_pos(
-                                ui.ctx()
-                                    .input(|i| i.pointer.hover_pos())
-                                    .unwrap_or_default(),
-                            )
-                            .show(ui.ctx(), |ui| {
-                                ui.add_enabled_ui(true, |ui| node.show(ui));
+                ui.add(egui::TextEdit::singleline(&mut self.node_list_search).hint_text("Search nodes"));
+                ui.checkbox(&mut self.compact_node_list, "Compact list");
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    if self.node_list_search.trim().is_empty() {
+                        let mut categories: Vec<&str> = Vec::new();
+                        for registered in self.registered_nodes.iter() {
+                            if !categories.contains(&registered.category.as_str()) {
+                                categories.push(&registered.category);
+                            }
+                        }
+                        for category in categories {
+                            egui::CollapsingHeader::new(category)
+                                .id_source(self.id.with("node list category").with(category))
+                                .default_open(true)
+                                .show(ui, |ui| {
+                                    for (index, registered) in self.registered_nodes.iter().enumerate() {
+                                        if registered.category == category {
+                                            if let Some(pos) = self.show_node_list_entry(ui, index) {
+                                                node_to_add = Some((index, pos));
+                                            }
+                                        }
+                                    }
+                                });
+                        }
+                    } else {
+                        for index in self.search_registered_nodes(&self.node_list_search) {
+                            if let Some(pos) = self.show_node_list_entry(ui, index) {
+                                node_to_add = Some((index, pos));
+                            }
+                        }
+                    }
+                });
+            });
+            offset = ui.cursor().left_top().to_vec2();
+            if let Some((index, pos)) = node_to_add {
+                let node = self.create_registered_node(index);
+                self.add_node(node, transform.inverse().mul_pos(pos - offset));
+            }
+        }
+
+        if self.inspector_panel_enabled {
+            egui::SidePanel::right(self.id.with("inspector"))
+                .default_width(220.0)
+                .show_inside(ui, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| self.show_inspector(ui));
+                });
+        }
+
+        if self.report_panel_enabled {
+            egui::SidePanel::right(self.id.with("report"))
+                .default_width(240.0)
+                .show_inside(ui, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| self.show_report_panel(ui));
+                });
+        }
+
+        if self.power_panel_enabled {
+            egui::TopBottomPanel::bottom(self.id.with("power")).show_inside(ui, |ui| self.show_power_panel(ui));
+        }
+
+        if self.surplus_panel_enabled {
+            egui::TopBottomPanel::bottom(self.id.with("surplus")).show_inside(ui, |ui| self.show_surplus_panel(ui));
+        }
+
+        let mut link_dropped = self.next_frame_link_dropped;
+        let mut dragged_node = None;
+        let mut drag_stopped_node = None;
+        let graph_rect = egui::CentralPanel::default()
+            .show_inside(ui, |ui| {
+                let (id, rect) = ui.allocate_space(ui.available_size());
+                self.canvas_rect = rect;
+                let response = ui.interact(rect, id, Sense::click_and_drag());
+                response.widget_info(|| WidgetInfo::labeled(WidgetType::Other, true, format!("Node graph, {} nodes", self.nodes.len())));
+                ui.ctx().accesskit_node_builder(id, |builder| builder.set_role(Role::Canvas));
+                if response.clicked() || response.drag_started() {
+                    self.make_active(ui.ctx());
+                }
+                let command = ui.input(|i| i.modifiers.command);
+                if response.drag_started() && ui.input(|i| i.modifiers.shift) {
+                    let transform = TSTransform::from_translation(ui.min_rect().left_top().to_vec2())
+                        * self.transform;
+                    self.box_select_start = ui.input(|i| i.pointer.press_origin()).map(|pos| transform.inverse() * pos);
+                }
+                if response.dragged() && self.box_select_start.is_none() {
+                    self.transform.translation += response.drag_delta()
+                }
+                if response.clicked() && !command {
+                    self.clear_selection();
+                }
+                response.context_menu(|ui| {
+                    let (shortcuts, ctx) = (self.shortcuts.clone(), ui.ctx().clone());
+                    let shortcut_text = move |action| shortcuts.shortcut_text(&ctx, action);
+                    if ui.add(egui::Button::new("Select all").shortcut_text(shortcut_text(Action::SelectAll))).clicked() {
+                        self.select_all();
+                        ui.close_menu();
+                    }
+                    ui.add_enabled_ui(!self.selection.is_empty(), |ui| {
+                        if ui.add(egui::Button::new("Duplicate").shortcut_text(shortcut_text(Action::Duplicate))).clicked() {
+                            self.duplicate_selection();
+                            ui.close_menu();
+                        }
+                        if ui.add(egui::Button::new("Delete").shortcut_text(shortcut_text(Action::Delete))).clicked() {
+                            self.delete_selection();
+                            ui.close_menu();
+                        }
+                        ui.menu_button("Align", |ui| {
+                            for (label, alignment) in [
+                                ("Left", Alignment::Left),
+                                ("Right", Alignment::Right),
+                                ("Top", Alignment::Top),
+                                ("Bottom", Alignment::Bottom),
+                            ] {
+                                if ui.button(label).clicked() {
+                                    self.align_selection(alignment);
+                                    ui.close_menu();
+                                }
+                            }
+                            ui.separator();
+                            if ui.button("Distribute horizontally").clicked() {
+                                self.distribute_selection_horizontally();
+                                ui.close_menu();
+                            }
+                            if ui.button("Distribute vertically").clicked() {
+                                self.distribute_selection_vertically();
+                                ui.close_menu();
+                            }
+                        });
+                    });
+                    ui.menu_button("View", |ui| {
+                        if ui.add(egui::Button::new("Frame all").shortcut_text(shortcut_text(Action::FrameAll))).clicked() {
+                            self.frame_all();
+                            ui.close_menu();
+                        }
+                        if ui.add_enabled(!self.selection.is_empty(), egui::Button::new("Frame selection")).clicked() {
+                            self.frame_selection();
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        let mut chosen_bookmark = None;
+                        let mut removed_bookmark = None;
+                        for (name, _) in self.bookmarks.iter() {
+                            ui.horizontal(|ui| {
+                                if ui.button(name).clicked() {
+                                    chosen_bookmark = Some(name.clone());
+                                    ui.close_menu();
+                                }
+                                if ui.small_button("x").on_hover_text("Remove bookmark").clicked() {
+                                    removed_bookmark = Some(name.clone());
+                                }
+                            });
+                        }
+                        if let Some(name) = chosen_bookmark {
+                            self.go_to_bookmark(&name);
+                        }
+                        if let Some(name) = removed_bookmark {
+                            self.remove_bookmark(&name);
+                        }
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut self.new_bookmark_name);
+                            if ui.add_enabled(!self.new_bookmark_name.is_empty(), egui::Button::new("Add bookmark")).clicked() {
+                                let name = std::mem::take(&mut self.new_bookmark_name);
+                                self.add_bookmark(name);
+                            }
+                        });
+                    });
+                    ui.separator();
+                    if ui.button("Add comment frame").clicked() {
+                        self.add_comment_frame_around_selection();
+                        ui.close_menu();
+                    }
+                    if ui.button("Add note").clicked() {
+                        let position = self.view_center();
+                        let comment_key = self.add_note(position, "Note");
+                        self.editing_comment = Some(comment_key);
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Auto layout").clicked() {
+                        self.auto_layout(false);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(!self.selection.is_empty(), egui::Button::new("Auto layout selection")).clicked() {
+                        self.auto_layout(true);
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.grid_enabled, "Show grid");
+                    ui.checkbox(&mut self.snap_to_grid, "Snap to grid");
+                    ui.horizontal(|ui| {
+                        ui.label("Grid spacing");
+                        let mut spacing = self.grid_spacing();
+                        if ui.add(egui::DragValue::new(&mut spacing).range(MIN_GRID_SPACING..=200.0)).changed() {
+                            self.set_grid_spacing(spacing);
+                        }
+                    });
+                    ui.checkbox(&mut self.minimap_enabled, "Show minimap");
+                    ui.horizontal(|ui| {
+                        ui.label("Theme");
+                        for (name, theme) in [("Dark", Theme::dark()), ("Light", Theme::light())] {
+                            if ui.selectable_label(self.theme == theme, name).clicked() {
+                                self.theme = theme;
+                            }
+                        }
+                    });
+                });
+                let transform = TSTransform::from_translation(ui.min_rect().left_top().to_vec2())
+                    * self.transform;
+                if let Some(start) = self.box_select_start {
+                    let end = transform.inverse() * response.interact_pointer_pos().unwrap_or_default();
+                    let selection_rect = Rect::from_two_pos(start, end);
+                    if response.drag_stopped() {
+                        if !command {
+                            self.selection.clear();
+                        }
+                        self.selection.extend(
+                            self.nodes
+                                .iter()
+                                // The cached rect is moved along with the position, in case the node was moved while culled
+                                .filter(|(_, node_information)| {
+                                    node_information.rect.is_some_and(|rect| Rect::from_min_size(node_information.position, rect.size()).intersects(selection_rect))
+                                })
+                                .map(|(node_key, _)| node_key),
+                        );
+                        self.box_select_start = None;
+                    } else {
+                        let selection_visuals = ui.visuals().selection;
+                        ui.painter().rect(
+                            transform * selection_rect,
+                            0.0,
+                            selection_visuals.bg_fill.gamma_multiply(0.25),
+                            selection_visuals.stroke,
+                        );
+                    }
+                }
+                if self.is_active(ui.ctx()) {
+                    let ctx = ui.ctx().clone();
+                    if self.shortcuts.consume(&ctx, Action::Delete) {
+                        self.delete_selection();
+                    }
+                    if self.shortcuts.consume(&ctx, Action::Duplicate) {
+                        self.duplicate_selection();
+                    }
+                    if self.shortcuts.consume(&ctx, Action::SelectAll) {
+                        self.select_all();
+                    }
+                    if self.shortcuts.consume(&ctx, Action::FrameAll) {
+                        self.frame_all();
+                    }
+                    let nudge = if self.snap_to_grid { self.grid_spacing } else { 10.0 };
+                    for (action, direction) in [
+                        (Action::NudgeLeft, Vec2::LEFT),
+                        (Action::NudgeRight, Vec2::RIGHT),
+                        (Action::NudgeUp, Vec2::UP),
+                        (Action::NudgeDown, Vec2::DOWN),
+                    ] {
+                        if self.shortcuts.consume(&ctx, action) {
+                            self.move_selection(direction * nudge);
+                        }
+                    }
+                    // Checked first, as shortcuts with fewer modifiers also match when extra modifiers are held
+                    if self.shortcuts.consume(&ctx, Action::FocusPrevious) {
+                        self.cycle_keyboard_focus(true);
+                    }
+                    if self.shortcuts.consume(&ctx, Action::FocusNext) {
+                        self.cycle_keyboard_focus(false);
+                    }
+                    if self.shortcuts.consume(&ctx, Action::StartLink) {
+                        self.start_keyboard_link();
+                    }
+                    if ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape)) {
+                        self.keyboard_link_start = None;
+                    }
+                    if self.shortcuts.consume(&ctx, Action::QuickAdd) {
+                        if let Some(pointer) = ui.input(|i| i.pointer.hover_pos()).filter(|pointer| rect.contains(*pointer)) {
+                            self.quick_add = Some(QuickAdd {
+                                position: transform.inverse() * pointer,
+                                search: String::new(),
+                                link: None,
                             });
+                        }
                     }
-                    if response.drag_stopped() {
-                        if let Some(pos) = ui.ctx().input(|i| i.pointer.interact_pos()) {
-                            node_to_add = Some((new_node_func(self.id.with("new node").with(self.new_node_id_source), &node), pos));
-                            self.new_node_id_source += 1;
+                    for event in ui.input(|i| i.events.clone()) {
+                        match event {
+                            Event::Copy if !self.selection.is_empty() => ui.ctx().copy_text(self.copy_selection()),
+                            Event::Cut if !self.selection.is_empty() => {
+                                ui.ctx().copy_text(self.copy_selection());
+                                self.delete_selection();
+                            }
+                            Event::Paste(text) => {
+                                let pointer = ui.input(|i| i.pointer.hover_pos()).unwrap_or(rect.center());
+                                // Anything on the clipboard that isn't a set of nodes is ignored
+                                let _ = self.paste(&text, transform.inverse() * pointer);
+                            }
+                            _ => {}
                         }
                     }
                 }
-            });
-            offset = ui.cursor().left_top().to_vec2();
-            if let Some((node, pos)) = node_to_add {
-                self.add_node(node, transform.inverse().mul_pos(pos - offset));
-            }
-        }
-
-        let mut link_dropped = self.next_frame_link_dropped;
-        let graph_rect = egui::CentralPanel::default()
-            .show_inside(ui, |ui| {
-                let (id, rect) = ui.allocate_space(ui.available_size());
-                let response = ui.interact(rect, id, Sense::click_and_drag());
-                if response.dragged() {
-                    self.transform.translation += response.drag_delta()
-                }
-                if response.secondary_clicked() {}
-                let transform = TSTransform::from_translation(ui.min_rect().left_top().to_vec2())
-                    * self.transform;
                 if let Some(pointer) = ui.ctx().input(|i| i.pointer.hover_pos()) {
                     let pointer_in_layer = transform.inverse() * pointer;
                     let zoom_delta = ui.ctx().input(|i| i.zoom_delta());
+                    let zoom_delta = (self.transform.scaling * zoom_delta).clamp(MIN_ZOOM, MAX_ZOOM) / self.transform.scaling;
                     let pan_delta = ui.ctx().input(|i| i.smooth_scroll_delta);
                     // Zoom in on pointer:
                     self.transform = self.transform
@@ -156,21 +1640,91 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
                     self.transform = TSTransform::from_translation(pan_delta) * self.transform;
                 }
 
+                ui.painter().rect_filled(rect, 0.0, self.theme.canvas_fill);
+                if self.grid_enabled {
+                    self.paint_grid(ui, rect, transform);
+                }
+                self.show_comments(ui, transform);
+
+                // Descriptions for screen readers are worked out up front, as the nodes stay borrowed once shown
+                let titles: HashMap<NodeKey, String> = self.nodes.iter().map(|(node_key, node_information)| (node_key, node_information.node.title().to_owned())).collect();
+                let mut connections: HashMap<(NodeKey, bool, usize), Vec<&str>> = HashMap::new();
+                for link in self.links.values() {
+                    connections.entry((link.input.0, true, link.input.1)).or_default().push(&titles[&link.output.0]);
+                    connections.entry((link.output.0, false, link.output.1)).or_default().push(&titles[&link.input.0]);
+                }
+                let link_labels: HashMap<LinkKey, String> = self.links.iter().map(|(link_key, link)| (link_key, self.link_label(link))).collect();
+
                 let mut input_info_slotmap = SlotMap::new();
                 let mut input_info_keys = Vec::new();
                 let mut output_info_slotmap = SlotMap::new();
                 let mut output_info_keys = Vec::new();
+                let viewport = transform.inverse() * rect;
+                let mut power_summary = PowerSummary::default();
+                let mut supplies = Vec::new();
+                let mut demands = Vec::new();
                 for (node_key, node_information) in self.nodes.iter_mut() {
+                    let (draw, supply) = (node_information.node.power_draw(), node_information.node.power_supply());
+                    if draw != 0.0 || supply != 0.0 {
+                        power_summary.nodes.push((node_key, draw, supply));
+                    }
+                    for index in 0..node_information.output_types.len() {
+                        if let Some(supply) = node_information.node.output_supply(index) {
+                            supplies.push(((node_key, index), supply));
+                        }
+                    }
+                    for index in 0..node_information.input_types.len() {
+                        if let Some(demand) = node_information.node.input_demand(index) {
+                            demands.push(((node_key, index), demand));
+                        }
+                    }
+                    // The cached rect is moved along with the position, in case the node was moved while culled
+                    let node_rect = node_information.rect.map(|rect| Rect::from_min_size(node_information.position, rect.size()));
+                    if let Some(node_rect) = node_rect.filter(|node_rect| !node_rect.intersects(viewport)) {
+                        // Off screen nodes skip painting, but are still shown into an invisible ui,
+                        // as some nodes do their work in their body, like the nested graph of a `GraphNode`
+                        // They take part in evaluation using the port positions from the last time they were painted
+                        let offset = node_rect.min - node_information.rect.unwrap_or(node_rect).min;
+                        let mut hidden_ui = ui.child_ui_with_id_source(node_rect, egui::Layout::default(), ("culled node", node_key), None);
+                        hidden_ui.set_invisible();
+                        let (input_info, output_info) = node_information.node.show(&mut hidden_ui, &mut node_information.display_state, &self.theme);
+                        for (i, (t, _, _, callback)) in input_info.into_iter().enumerate() {
+                            let pos = node_information.input_positions.get(i).map_or(node_rect.left_center(), |pos| *pos + offset);
+                            input_info_keys.push((node_key, t, input_info_slotmap.insert((pos, callback))));
+                        }
+                        for (i, (t, _, _, callback)) in output_info.into_iter().enumerate() {
+                            let pos = node_information.output_positions.get(i).map_or(node_rect.right_center(), |pos| *pos + offset);
+                            output_info_keys.push((node_key, t, output_info_slotmap.insert((pos, callback))));
+                        }
+                        continue;
+                    }
                     let window_layer = ui.layer_id();
-                    let id = egui::Area::new(id.with(self.id).with(node_key))
-                        .default_pos(node_information.position)
+                    // A graph shown into an invisible ui, like the one of a culled `GraphNode`, keeps its nodes invisible too
+                    let visible = ui.is_visible();
+                    // While snapping, the unsnapped position is kept so small drags can add up
+                    let shown_position = if self.snap_to_grid {
+                        snap_to_grid(node_information.position, self.grid_spacing)
+                    } else {
+                        node_information.position
+                    };
+                    let response = egui::Area::new(id.with(self.id).with(node_key))
+                        .current_pos(shown_position)
+                        .sense(Sense::click_and_drag())
                         .order(egui::Order::Middle)
                         .constrain(false)
+                        .interactable(visible)
                         .show(ui.ctx(), |ui| {
+                            if !visible {
+                                ui.set_invisible();
+                            }
                             ui.set_clip_rect(transform.inverse() * rect);
-                            let (input_info, output_info) = node_information.node.show(ui);
-                            for (i, (t, pos, callback)) in input_info.into_iter().enumerate() {
-                                input_info_keys.push((node_key, input_info_slotmap.insert((i, pos, callback))));
+                            let (input_info, output_info) = node_information.node.show(ui, &mut node_information.display_state, &self.theme);
+                            node_information.input_positions = input_info.iter().map(|(_, _, pos, _)| *pos).collect();
+                            node_information.output_positions = output_info.iter().map(|(_, _, pos, _)| *pos).collect();
+                            node_information.input_types = input_info.iter().map(|(t, type_name, _, _)| (*t, *type_name)).collect();
+                            node_information.output_types = output_info.iter().map(|(t, type_name, _, _)| (*t, *type_name)).collect();
+                            for (i, (t, type_name, pos, callback)) in input_info.into_iter().enumerate() {
+                                input_info_keys.push((node_key, t, input_info_slotmap.insert((pos, callback))));
                                 let response = ui.interact(
                                     Rect::from_two_pos(
                                         pos - Vec2::new(5.0, 5.0),
@@ -179,6 +1733,8 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
                                     ui.id().with("input").with(i),
                                     Sense::drag(),
                                 );
+                                let connected_to = connections.get(&(node_key, true, i)).map_or(&[][..], Vec::as_slice);
+                                response.widget_info(|| WidgetInfo::labeled(WidgetType::Button, true, port_label(&titles[&node_key], true, i, type_name, connected_to)));
                                 if response.drag_started() {
                                     self.link_drag_info = Some((node_key, t, pos, true, i));
                                 }
@@ -195,7 +1751,7 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
                                 {
                                     link_dropped = false;
                                     self.next_frame_link_dropped = false;
-                                    self.links.push((
+                                    insert_link(&mut self.links, LinkInformation::new(
                                         (node_key, i),
                                         (
                                             self.link_drag_info.unwrap().0,
@@ -205,8 +1761,8 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
                                     self.link_drag_info = None;
                                 }
                             }
-                            for (i, (t, pos, callback)) in output_info.into_iter().enumerate() {
-                                output_info_keys.push((node_key, output_info_slotmap.insert((i, pos, callback))));
+                            for (i, (t, type_name, pos, callback)) in output_info.into_iter().enumerate() {
+                                output_info_keys.push((node_key, t, output_info_slotmap.insert((pos, callback))));
                                 let response = ui.interact(
                                     Rect::from_two_pos(
                                         pos - Vec2::new(5.0, 5.0),
@@ -215,6 +1771,8 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
                                     ui.id().with("output").with(i),
                                     Sense::drag(),
                                 );
+                                let connected_to = connections.get(&(node_key, false, i)).map_or(&[][..], Vec::as_slice);
+                                response.widget_info(|| WidgetInfo::labeled(WidgetType::Button, true, port_label(&titles[&node_key], false, i, type_name, connected_to)));
                                 if response.drag_started() {
                                     self.link_drag_info = Some((node_key, t, pos, false, i));
                                 }
@@ -231,7 +1789,7 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
                                 {
                                     link_dropped = false;
                                     self.next_frame_link_dropped = false;
-                                    self.links.push((
+                                    insert_link(&mut self.links, LinkInformation::new(
                                         (
                                             self.link_drag_info.unwrap().0,
                                             self.link_drag_info.unwrap().4,
@@ -242,30 +1800,183 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
                                 }
                             }
                         })
-                        .response
-                        .layer_id;
-                    ui.ctx().set_transform_layer(id, transform);
-                    ui.ctx().set_sublayer(window_layer, id);
-                }
-                for ((start_key, start_index), (end_key, end_index)) in self.links.iter() {
-                    let start = input_info_keys
-                        .iter()
-                        .filter(|x| x.0 == *start_key)
-                        .nth(*start_index)
-                        .unwrap();
-                    let end = output_info_keys
-                        .iter()
-                        .filter(|x| x.0 == *end_key)
-                        .nth(*end_index)
-                        .unwrap();
-                    let start = input_info_slotmap.remove(start.1).unwrap();
-                    let end = output_info_slotmap.remove(end.1).unwrap();
-                    ui.painter().line_segment(
-                        [transform.mul_pos(start.1), transform.mul_pos(end.1)],
-                        (3.0, Color32::YELLOW),
-                    );
-                    start.2(end.2());
+                        .response;
+                    node_information.rect = Some(response.rect);
+                    response.widget_info(|| {
+                        let mut label = format!(
+                            "{}, node, {} inputs, {} outputs",
+                            titles[&node_key],
+                            node_information.input_positions.len(),
+                            node_information.output_positions.len(),
+                        );
+                        if self.selection.contains(&node_key) {
+                            label += ", selected";
+                        }
+                        if node_information.display_state.collapsed {
+                            label += ", collapsed";
+                        }
+                        WidgetInfo::labeled(WidgetType::Other, true, label)
+                    });
+                    ui.ctx().accesskit_node_builder(response.id, |builder| builder.set_role(Role::Group));
+                    if response.clicked() || response.drag_started() {
+                        if command {
+                            if response.clicked() && !self.selection.remove(&node_key) {
+                                self.selection.insert(node_key);
+                            }
+                        } else if !(response.drag_started() && self.selection.contains(&node_key)) {
+                            self.selection.clear();
+                            self.selection.insert(node_key);
+                        }
+                    }
+                    if response.dragged() {
+                        dragged_node = Some((node_key, response.rect.min - shown_position));
+                    }
+                    if response.drag_stopped() {
+                        drag_stopped_node = Some(node_key);
+                    }
+                    if visible && self.selection.contains(&node_key) {
+                        ui.ctx().layer_painter(response.layer_id).rect_stroke(
+                            response.rect.expand(2.0),
+                            4.0,
+                            Stroke::new(2.0, ui.visuals().selection.stroke.color),
+                        );
+                    }
+                    ui.ctx().set_transform_layer(response.layer_id, transform);
+                    ui.ctx().set_sublayer(window_layer, response.layer_id);
+                }
+                let link_stroke = Stroke::new(3.0, self.theme.link_color);
+                let mut double_click_handled = false;
+                // Nodes can change their ports, such as a machine given a new recipe,
+                // so links to ports that are gone or now carry another type are skipped until the user removes them
+                let mut broken_links = HashSet::new();
+                let mut working_links = HashMap::new();
+                for (link_key, link) in self.links.iter() {
+                    let start = input_info_keys.iter().filter(|x| x.0 == link.input.0).nth(link.input.1);
+                    let end = output_info_keys.iter().filter(|x| x.0 == link.output.0).nth(link.output.1);
+                    match (start, end) {
+                        (Some(&(_, start_type, start)), Some(&(_, end_type, end))) if start_type == end_type => {
+                            working_links.insert(link_key, (start, end));
+                        }
+                        _ => {
+                            broken_links.insert(link_key);
+                        }
+                    }
+                }
+                // What each link carried after splits and pipe limits, for working out the surplus
+                let mut carried_flows = HashMap::new();
+                // Outputs linked more than once split what they make between their working links
+                let mut fan_outs: HashMap<(NodeKey, usize), Vec<LinkKey>> = HashMap::new();
+                let mut link_demands = HashMap::new();
+                for (link_key, link) in self.links.iter().filter(|(link_key, _)| working_links.contains_key(link_key)) {
+                    fan_outs.entry(link.output).or_default().push(link_key);
+                    link_demands.insert(link_key, demands.iter().find(|(port, _)| *port == link.input).map(|(_, demand)| demand));
+                }
+                for (link_key, link) in self.links.iter_mut() {
+                    let Some(&(start, end)) = working_links.get(&link_key) else {
+                        continue;
+                    };
+                    // Inputs take a single value, so an input that already took one is skipped
+                    let (Some(start), Some(end)) = (input_info_slotmap.remove(start), output_info_slotmap.get(end)) else {
+                        continue;
+                    };
+                    let mut value = end.1();
+                    if let Some(linked) = fan_outs.get(&link.output).filter(|linked| linked.len() > 1) {
+                        let demands: Vec<_> = linked.iter().map(|key| link_demands[key]).collect();
+                        let position = linked.iter().position(|key| *key == link_key).unwrap_or_default();
+                        if let Some(flow) = value.downcast_mut::<ItemFlow>() {
+                            flow.per_minute = split_flow(flow, &demands)[position];
+                        } else if let Some(flow) = value.downcast_mut::<FluidFlow>() {
+                            let as_item = ItemFlow { item: flow.fluid.clone(), per_minute: flow.per_minute };
+                            flow.per_minute = split_flow(&as_item, &demands)[position];
+                        }
+                    }
+                    // Links carrying fluid are pipes, which carry less the longer they are
+                    // `pipe_limited` is `None` for other links, and whether the fluid was held back for pipes
+                    let throughput = pipe_throughput(link.pipe_length);
+                    let pipe_limited = value.downcast_mut::<FluidFlow>().map(|flow| {
+                        let limited = flow.per_minute > throughput;
+                        flow.per_minute = flow.per_minute.min(throughput);
+                        limited
+                    });
+                    let carried = value
+                        .downcast_ref::<ItemFlow>()
+                        .map(|flow| flow.per_minute)
+                        .or_else(|| value.downcast_ref::<FluidFlow>().map(|flow| flow.per_minute));
+                    carried_flows.insert(link_key, carried);
+                    let link_stroke = if pipe_limited.is_some() {
+                        Stroke::new(link_stroke.width, self.theme.pipe_color)
+                    } else {
+                        link_stroke
+                    };
+                    // Links have no widget of their own, so they are only described to screen readers
+                    ui.ctx().accesskit_node_builder(id.with("link").with(link_key), |builder| {
+                        builder.set_role(Role::GraphicsObject);
+                        builder.set_name(link_labels[&link_key].as_str());
+                    });
+
+                    let mut removed_reroute = None;
+                    for (i, reroute) in link.reroutes.iter_mut().enumerate() {
+                        let reroute_response = ui.interact(
+                            Rect::from_center_size(transform * *reroute, Vec2::splat(10.0)),
+                            id.with("reroute").with(link_key).with(i),
+                            Sense::click_and_drag(),
+                        );
+                        *reroute += reroute_response.drag_delta() / transform.scaling;
+                        if reroute_response.double_clicked() {
+                            removed_reroute = Some(i);
+                        }
+                    }
+                    if let Some(i) = removed_reroute {
+                        link.reroutes.remove(i);
+                    }
+
+                    let points: Vec<_> = std::iter::once(end.0)
+                        .chain(link.reroutes.iter().copied())
+                        .chain(std::iter::once(start.0))
+                        .map(|pos| transform * pos)
+                        .collect();
+                    for (segment, pair) in points.windows(2).enumerate() {
+                        let bezier = link_bezier(pair[0], pair[1], link_stroke);
+                        // Double clicking a link adds a reroute point to shape its path
+                        if let Some(pointer) = response.interact_pointer_pos().filter(|_| response.double_clicked() && !double_click_handled) {
+                            if distance_to_line(&bezier.flatten(Some(1.0)), pointer) <= 6.0 {
+                                link.reroutes.insert(segment, transform.inverse() * pointer);
+                                double_click_handled = true;
+                            }
+                        }
+                        ui.painter().add(bezier);
+                    }
+                    for reroute in points[1..points.len() - 1].iter() {
+                        ui.painter().circle_filled(*reroute, 5.0, link_stroke.color);
+                    }
+                    if let Some(limited) = pipe_limited {
+                        let middle = (points.len() - 1) / 2;
+                        let label_position = link_bezier(points[middle], points[middle + 1], link_stroke).sample(0.5);
+                        let color = if limited { ui.visuals().error_fg_color } else { ui.visuals().text_color() };
+                        ui.painter().text(
+                            label_position - Vec2::new(0.0, 4.0),
+                            Align2::CENTER_BOTTOM,
+                            format!("{} pipes, up to {}/min", link.pipe_length, format_rate(throughput)),
+                            FontId::proportional(11.0 * transform.scaling),
+                            color,
+                        );
+                    }
+                    start.1(value);
+                }
+                // Broken links are drawn dashed between where their ports were last shown, so they can be found and removed
+                // The callbacks left over borrow the nodes, so they go first
+                drop((input_info_slotmap, output_info_slotmap));
+                let broken_stroke = Stroke::new(2.0, ui.visuals().error_fg_color);
+                for link_key in broken_links.iter() {
+                    let link = &self.links[*link_key];
+                    let from = transform * self.port_position(link.output.0, link.output.1, false);
+                    let to = transform * self.port_position(link.input.0, link.input.1, true);
+                    ui.painter().extend(egui::Shape::dashed_line(&[from, to], broken_stroke, 8.0, 6.0));
                 }
+                self.power_summary = power_summary;
+                let carried_links = self.links.iter().filter_map(|(link_key, link)| Some((link, *carried_flows.get(&link_key)?)));
+                self.surplus_summary = SurplusSummary::new(supplies, demands, carried_links);
+                self.broken_links = broken_links;
                 // if ui.ctx().input(|i| i.pointer.primary_clicked()) {
                 //     dbg!(ui.ctx().input(|i| i.pointer.interact_pos()));
                 // }
@@ -281,26 +1992,499 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
             .response
             .rect;
 
-        if let Some((_, _, pos, _, _)) = self.link_drag_info {
+        if let Some((node_key, delta)) = dragged_node {
+            self.make_active(ui.ctx());
+            if self.selection.contains(&node_key) {
+                self.move_selection(delta);
+            } else {
+                self.nodes[node_key].position += delta;
+            }
+        }
+        if let Some(node_key) = drag_stopped_node.filter(|_| self.snap_to_grid) {
+            let moved_nodes = if self.selection.contains(&node_key) {
+                self.selection.iter().copied().collect()
+            } else {
+                vec![node_key]
+            };
+            for node_key in moved_nodes {
+                self.nodes[node_key].position = snap_to_grid(self.nodes[node_key].position, self.grid_spacing);
+            }
+        }
+
+        // These are painted on their own layers, which don't know when the graph is shown into an invisible ui
+        if ui.is_visible() {
+            if self.minimap_enabled {
+                self.show_minimap(ui.ctx());
+            }
+            self.show_quick_add(ui.ctx());
+            self.paint_keyboard_focus(ui.ctx(), self.canvas_transform(), self.canvas_rect);
+        }
+
+        if let Some((_, _, pos, from_input, _)) = self.link_drag_info {
+            let port = transform.mul_pos(pos + offset);
+            let pointer = ui.ctx().input(|i| i.pointer.hover_pos().unwrap_or_default());
+            let (from, to) = if from_input { (pointer, port) } else { (port, pointer) };
             ui.ctx()
                 .layer_painter(LayerId::new(
                     egui::Order::Foreground,
                     self.id.with("drag line painter layer"),
                 ))
                 .with_clip_rect(graph_rect)
-                .line_segment(
-                    [
-                        transform.mul_pos(pos + offset),
-                        (ui.ctx()
-                            .input(|i| i.pointer.hover_pos().unwrap_or_default())),
-                    ],
-                    (3.0, Color32::YELLOW),
-                );
+                .add(link_bezier(from, to, Stroke::new(3.0, self.theme.link_color)));
         }
 
         if link_dropped {
             self.next_frame_link_dropped = false;
-            self.link_drag_info = None;
+            if let Some((node_key, port_type, _, from_input, index)) = self.link_drag_info.take() {
+                let pointer = ui.ctx().input(|i| i.pointer.hover_pos()).filter(|pointer| graph_rect.contains(*pointer));
+                if let Some(pointer) = pointer {
+                    // The canvas is offset by the node list, which the outer `transform` doesn't account for
+                    let position = self.canvas_transform().inverse() * pointer;
+                    let over_node = self.nodes.values().any(|node| node.rect.is_some_and(|rect| rect.contains(position)));
+                    if !over_node {
+                        self.quick_add = Some(QuickAdd {
+                            position,
+                            search: String::new(),
+                            link: Some(DroppedLink { node_key, port_type, from_input, index }),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Shows the properties, links and errors of the selected node
+    fn show_inspector(&mut self, ui: &mut Ui) {
+        let node_key = match self.selection.iter().collect::<Vec<_>>().as_slice() {
+            [node_key] => **node_key,
+            [] => {
+                ui.weak("Select a node to inspect it");
+                return;
+            }
+            selection => {
+                ui.weak(format!("{} nodes selected", selection.len()));
+                return;
+            }
+        };
+        let node_information = &mut self.nodes[node_key];
+        ui.heading(node_information.node.title());
+        ui.weak(short_type_name(node_information.node.type_name()));
+        ui.separator();
+        node_information.node.inspector(ui);
+
+        ui.separator();
+        ui.strong("Links");
+        let title = |node_key: NodeKey| self.nodes.get(node_key).map_or("missing node", |node| node.node.title());
+        let carries_fluid = |link: &LinkInformation| {
+            self.nodes
+                .get(link.output.0)
+                .and_then(|node| node.output_types.get(link.output.1))
+                .is_some_and(|(t, _)| *t == TypeId::of::<FluidFlow>())
+        };
+        let mut removed_link = None;
+        let mut any_links = false;
+        for (link_key, link) in self.links.iter_mut() {
+            let text = if link.input.0 == node_key {
+                format!("Input {} from {} output {}", link.input.1 + 1, title(link.output.0), link.output.1 + 1)
+            } else if link.output.0 == node_key {
+                format!("Output {} to {} input {}", link.output.1 + 1, title(link.input.0), link.input.1 + 1)
+            } else {
+                continue;
+            };
+            any_links = true;
+            ui.horizontal(|ui| {
+                if self.broken_links.contains(&link_key) {
+                    ui.colored_label(ui.visuals().error_fg_color, text)
+                        .on_hover_text("The ports at the ends of this link are gone or no longer match, so it carries nothing");
+                } else {
+                    ui.label(text);
+                }
+                if ui.small_button("x").on_hover_text("Remove link").clicked() {
+                    removed_link = Some(link_key);
+                }
+            });
+            if carries_fluid(link) {
+                ui.horizontal(|ui| {
+                    ui.label("Pipe length");
+                    ui.add(egui::DragValue::new(&mut link.pipe_length).range(1..=MAX_PIPE_LENGTH));
+                    ui.weak(format!("up to {}/min", format_rate(pipe_throughput(link.pipe_length))));
+                });
+            }
+        }
+        if !any_links {
+            ui.weak("Not connected");
+        }
+        if let Some(link_key) = removed_link {
+            self.links.remove(link_key);
+        }
+
+        let errors = self.nodes[node_key].node.errors();
+        if !errors.is_empty() {
+            ui.separator();
+            ui.strong("Errors");
+            for error in errors {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        }
+    }
+
+    /// Shows the total power drawn and generated, and which nodes draw and generate it
+    fn show_power_panel(&mut self, ui: &mut Ui) {
+        let (draw, supply) = (self.power_summary.draw(), self.power_summary.supply());
+        ui.horizontal(|ui| {
+            ui.strong("Power");
+            ui.label(format!("Generated {}", format_power(supply)));
+            ui.label(format!("Drawn {}", format_power(draw)));
+            if draw > supply {
+                let satisfaction = if draw > 0.0 { supply / draw * 100.0 } else { 0.0 };
+                ui.colored_label(ui.visuals().error_fg_color, format!("Short by {}, only {satisfaction:.0}% of the draw is covered", format_power(draw - supply)));
+            } else {
+                ui.label(format!("Spare {}", format_power(supply - draw)));
+            }
+        });
+        egui::CollapsingHeader::new("Nodes").id_source(self.id.with("power nodes")).show(ui, |ui| {
+            let mut nodes = self.power_summary.nodes.clone();
+            nodes.sort_by(|a, b| (b.1 + b.2).total_cmp(&(a.1 + a.2)));
+            for (node_key, draw, supply) in nodes {
+                let Some(node_information) = self.nodes.get(node_key) else {
+                    continue;
+                };
+                let text = match (draw != 0.0, supply != 0.0) {
+                    (true, true) => format!("{}: draws {}, generates {}", node_information.node.title(), format_power(draw), format_power(supply)),
+                    (true, false) => format!("{}: draws {}", node_information.node.title(), format_power(draw)),
+                    _ => format!("{}: generates {}", node_information.node.title(), format_power(supply)),
+                };
+                if ui.selectable_label(self.selection.contains(&node_key), text).clicked() {
+                    self.selection.clear();
+                    self.selection.insert(node_key);
+                }
+            }
+        });
+    }
+
+    /// Shows the surplus of each item, and the outputs it comes from with ways of dealing with it
+    fn show_surplus_panel(&mut self, ui: &mut Ui) {
+        // (item, left over, voided)
+        let mut totals: Vec<(&str, f64, f64)> = Vec::new();
+        for surplus in self.surplus_summary.surpluses.iter() {
+            let index = match totals.iter().position(|(item, _, _)| *item == surplus.item) {
+                Some(index) => index,
+                None => {
+                    totals.push((&surplus.item, 0.0, 0.0));
+                    totals.len() - 1
+                }
+            };
+            if surplus.voided {
+                totals[index].2 += surplus.per_minute;
+            } else {
+                totals[index].1 += surplus.per_minute;
+            }
+        }
+        ui.horizontal_wrapped(|ui| {
+            ui.strong("Surplus");
+            if totals.is_empty() {
+                ui.weak("Nothing is left over");
+            }
+            for (item, left_over, voided) in totals {
+                if left_over > 0.0 {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("{item}: {}/min left over", format_rate(left_over)));
+                }
+                if voided > 0.0 {
+                    ui.label(format!("{item}: {}/min voided", format_rate(voided)));
+                }
+            }
+        });
+
+        let title = |node_key: NodeKey| self.nodes.get(node_key).map_or("missing node", |node| node.node.title());
+        let mut void_output = None;
+        let mut recycle_link = None;
+        egui::CollapsingHeader::new("Outputs").id_source(self.id.with("surplus outputs")).show(ui, |ui| {
+            for surplus in self.surplus_summary.surpluses.iter().filter(|surplus| !surplus.voided) {
+                let (node_key, index) = surplus.output;
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} output {}: {}/min of {} left over", title(node_key), index + 1, format_rate(surplus.per_minute), surplus.item));
+                    if ui.small_button("Void").on_hover_text("Link the output to a new node that takes the excess").clicked() {
+                        void_output = Some(surplus.output);
+                    }
+                    ui.menu_button("Recycle into", |ui| {
+                        let candidates: Vec<_> = self
+                            .surplus_summary
+                            .demands
+                            .iter()
+                            .filter(|(input, flow)| flow.item == surplus.item && input.0 != node_key && !self.links.values().any(|link| link.input == *input))
+                            .collect();
+                        if candidates.is_empty() {
+                            ui.weak(format!("No unlinked input needs {}", surplus.item));
+                        }
+                        for (input, flow) in candidates {
+                            if ui.button(format!("{} input {}, needs {}/min", title(input.0), input.1 + 1, format_rate(flow.per_minute))).clicked() {
+                                recycle_link = Some(LinkInformation::new(*input, surplus.output));
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                });
+            }
+            for surplus in self.surplus_summary.surpluses.iter().filter(|surplus| surplus.voided) {
+                let (node_key, index) = surplus.output;
+                ui.weak(format!("{} output {}: {}/min of {} voided", title(node_key), index + 1, format_rate(surplus.per_minute), surplus.item));
+            }
+        });
+        // The summary is from the last frame, so the graph may have changed since
+        if let Some(link) = recycle_link.filter(|link| self.link_fits(link)) {
+            insert_link(&mut self.links, link);
+        }
+        if let Some(output) = void_output {
+            self.void_surplus(output);
+        }
+    }
+
+    /// Adds the first registered node with an input taking excess, like a `VoidNode`, and links `output` to it
+    fn void_surplus(&mut self, output: (NodeKey, usize)) {
+        let Some(output_type) = self.nodes.get(output.0).and_then(|node| node.output_types.get(output.1)).map(|(t, _)| *t) else {
+            return;
+        };
+        let found = (0..self.registered_nodes.len()).find_map(|index| {
+            let (inputs, _) = self.registered_node_ports(index);
+            let port = inputs
+                .iter()
+                .enumerate()
+                .position(|(i, input_type)| *input_type == output_type && self.registered_nodes[index].node.input_demand(i) == Some(Demand::Excess))?;
+            Some((index, port))
+        });
+        if let Some((index, port)) = found {
+            let position = self.node_rect(output.0).right_top() + Vec2::new(60.0, 0.0);
+            let node = self.create_registered_node(index);
+            let node_key = self.add_node(node, position);
+            insert_link(&mut self.links, LinkInformation::new((node_key, port), output));
+        }
+    }
+
+    /// Shows the bill of materials of the graph, with buttons for copying it as CSV or Markdown
+    /// followed by the construction cost, when the graph has a recipe database
+    fn show_report_panel(&mut self, ui: &mut Ui) {
+        let report = self.report();
+        ui.horizontal(|ui| {
+            ui.strong("Bill of materials");
+            if ui.small_button("Copy CSV").clicked() {
+                ui.ctx().copy_text(report.to_csv());
+            }
+            if ui.small_button("Copy Markdown").clicked() {
+                ui.ctx().copy_text(report.to_markdown());
+            }
+        });
+        let amounts = [("Raw resources", &report.raw_resources), ("Intermediate products", &report.intermediates), ("Products", &report.products)];
+        for (title, amounts) in amounts {
+            egui::CollapsingHeader::new(title).id_source(self.id.with("report").with(title)).default_open(true).show(ui, |ui| {
+                if amounts.is_empty() {
+                    ui.weak("None");
+                }
+                egui::Grid::new(self.id.with("report grid").with(title)).striped(true).show(ui, |ui| {
+                    for (name, amount) in amounts {
+                        ui.label(name);
+                        ui.label(format!("{}/min", format_rate(*amount)));
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+        egui::CollapsingHeader::new("Buildings").id_source(self.id.with("report buildings")).default_open(true).show(ui, |ui| {
+            if report.buildings.is_empty() {
+                ui.weak("None");
+            }
+            egui::Grid::new(self.id.with("report buildings grid")).striped(true).show(ui, |ui| {
+                for building in report.buildings.iter() {
+                    ui.label(&building.name);
+                    ui.label(building.count.to_string()).on_hover_text(format!("{} if they all ran at full speed", format_rate(building.exact)));
+                    ui.end_row();
+                }
+            });
+        });
+        ui.label(format!("Draws {}, generates {}", format_power(report.power_draw), format_power(report.power_supply)));
+
+        let Some(database) = self.recipe_database.clone() else {
+            return;
+        };
+        ui.separator();
+        let cost = ConstructionCost::new(&report, &database, &self.construction_settings);
+        ui.horizontal(|ui| {
+            ui.strong("Construction cost");
+            if ui.small_button("Copy CSV").clicked() {
+                ui.ctx().copy_text(cost.to_csv());
+            }
+            if ui.small_button("Copy Markdown").clicked() {
+                ui.ctx().copy_text(cost.to_markdown());
+            }
+        });
+        ui.checkbox(&mut self.construction_settings.recursive, "Down to raw resources");
+        ui.horizontal(|ui| {
+            ui.label("Belts per link");
+            ui.add(egui::DragValue::new(&mut self.construction_settings.belts_per_link).range(0..=1000));
+        });
+        for (title, amounts) in [("Pieces", &cost.pieces), ("Materials", &cost.materials)] {
+            egui::CollapsingHeader::new(title).id_source(self.id.with("construction").with(title)).default_open(true).show(ui, |ui| {
+                if amounts.is_empty() {
+                    ui.weak("None");
+                }
+                egui::Grid::new(self.id.with("construction grid").with(title)).striped(true).show(ui, |ui| {
+                    for (name, amount) in amounts {
+                        ui.label(name);
+                        ui.label(format_rate(*amount));
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+        if !cost.unknown.is_empty() {
+            ui.colored_label(ui.visuals().warn_fg_color, format!("Not in the recipe database: {}", cost.unknown.join(", ")));
+        }
+    }
+
+    /// What all the nodes of the graph bring in, make, use and need to be built, without combining anything
+    /// Links carrying items count as belts, and links carrying fluids as their pipes
+    /// The types of the links come from the bodies of the nodes, so nodes don't have to have been shown
+    pub fn node_report(&self) -> NodeReport {
+        let mut combined = NodeReport::default();
+        for node_information in self.nodes.values() {
+            let node_report = node_information.node.report();
+            combined.raw_resources.extend(node_report.raw_resources);
+            combined.produced.extend(node_report.produced);
+            combined.consumed.extend(node_report.consumed);
+            combined.buildings.extend(node_report.buildings);
+            combined.belt_links += node_report.belt_links;
+            combined.pipes += node_report.pipes;
+        }
+        let mut output_types = HashMap::new();
+        for link in self.links.values() {
+            let types = output_types
+                .entry(link.output.0)
+                .or_insert_with(|| self.nodes.get(link.output.0).map(|node| port_types(&*node.node).1));
+            match types.as_ref().and_then(|types| types.get(link.output.1)) {
+                Some(t) if *t == TypeId::of::<ItemFlow>() => combined.belt_links += 1,
+                Some(t) if *t == TypeId::of::<FluidFlow>() => combined.pipes += link.pipe_length,
+                _ => {}
+            }
+        }
+        combined
+    }
+
+    /// The bill of materials of the whole graph, including nested graphs
+    /// Unlike `power_draw`, this asks the nodes directly, so it works on graphs that were never shown
+    pub fn report(&self) -> Report {
+        let power_draw = self.nodes.values().map(|node_information| node_information.node.power_draw()).sum();
+        let power_supply = self.nodes.values().map(|node_information| node_information.node.power_supply()).sum();
+        Report::new([self.node_report()], power_draw, power_supply)
+    }
+
+    /// The power drawn by all the nodes of the graph, in watts, as of the last time it was shown
+    pub fn power_draw(&self) -> f64 {
+        self.power_summary.draw()
+    }
+
+    /// The power generated by all the nodes of the graph, in watts, as of the last time it was shown
+    pub fn power_supply(&self) -> f64 {
+        self.power_summary.supply()
+    }
+
+    /// The transform from graph space to screen space, as of the last frame
+    fn canvas_transform(&self) -> TSTransform {
+        TSTransform::from_translation(self.canvas_rect.min.to_vec2()) * self.transform
+    }
+
+    /// Shows every node and link scaled down in the corner of the canvas, along with the visible area
+    /// Clicking or dragging on the minimap moves the view there
+    fn show_minimap(&mut self, ctx: &Context) {
+        let canvas_rect = self.canvas_rect;
+        let transform = self.canvas_transform();
+        let viewport = transform.inverse() * canvas_rect;
+        let bounds = self
+            .nodes
+            .keys()
+            .map(|node_key| self.node_rect(node_key))
+            .fold(viewport, |bounds, rect| bounds.union(rect))
+            .expand(20.0);
+        let scale = (MINIMAP_SIZE.x / bounds.width()).min(MINIMAP_SIZE.y / bounds.height());
+        let minimap_rect = Rect::from_min_size(canvas_rect.max - MINIMAP_SIZE - Vec2::splat(10.0), MINIMAP_SIZE);
+        // Centers the scaled down graph inside the minimap
+        let minimap_transform = TSTransform::from_translation(minimap_rect.center().to_vec2())
+            * TSTransform::from_scaling(scale)
+            * TSTransform::from_translation(-bounds.center().to_vec2());
+
+        egui::Area::new(self.id.with("minimap"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(minimap_rect.min)
+            .show(ctx, |ui| {
+                ui.set_clip_rect(canvas_rect);
+                let response = ui.allocate_rect(minimap_rect, Sense::click_and_drag());
+                let painter = ui.painter_at(minimap_rect);
+                painter.rect(
+                    minimap_rect,
+                    4.0,
+                    ui.visuals().extreme_bg_color.gamma_multiply(0.9),
+                    ui.visuals().window_stroke,
+                );
+                for link in self.links.values() {
+                    painter.line_segment(
+                        [
+                            minimap_transform * self.port_position(link.output.0, link.output.1, false),
+                            minimap_transform * self.port_position(link.input.0, link.input.1, true),
+                        ],
+                        (1.0, self.theme.link_color),
+                    );
+                }
+                for node_key in self.nodes.keys() {
+                    let color = if self.selection.contains(&node_key) {
+                        ui.visuals().selection.bg_fill
+                    } else {
+                        ui.visuals().widgets.inactive.bg_fill
+                    };
+                    painter.rect_filled(minimap_transform * self.node_rect(node_key), 1.0, color);
+                }
+                painter.rect_stroke(minimap_transform * viewport, 0.0, ui.visuals().selection.stroke);
+                if let Some(pointer) = response.interact_pointer_pos().filter(|_| response.is_pointer_button_down_on()) {
+                    let target = minimap_transform.inverse() * pointer;
+                    self.transform.translation = canvas_rect.center() - canvas_rect.min - target.to_vec2() * self.transform.scaling;
+                }
+            });
+    }
+
+    /// Paints the background grid over `rect`
+    /// Lines get spread out further when zoomed out, so they never get too dense
+    fn paint_grid(&self, ui: &Ui, rect: Rect, transform: TSTransform) {
+        let mut spacing = self.grid_spacing;
+        while spacing * transform.scaling < 8.0 {
+            spacing *= 5.0;
+        }
+        let minor_color = self.theme.visuals.widgets.noninteractive.bg_stroke.color.gamma_multiply(0.4);
+        let major_color = self.theme.visuals.widgets.noninteractive.bg_stroke.color;
+        let viewport = transform.inverse() * rect;
+        let painter = ui.painter_at(rect);
+        for axis in 0..2 {
+            let mut line = (viewport.min[axis] / spacing).floor() as i64;
+            while line as f32 * spacing <= viewport.max[axis] {
+                let color = if line % 5 == 0 { major_color } else { minor_color };
+                let graph_position = line as f32 * spacing;
+                let screen_position = transform.translation[axis] + graph_position * transform.scaling;
+                let points = if axis == 0 {
+                    [Pos2::new(screen_position, rect.top()), Pos2::new(screen_position, rect.bottom())]
+                } else {
+                    [Pos2::new(rect.left(), screen_position), Pos2::new(rect.right(), screen_position)]
+                };
+                painter.line_segment(points, (1.0, color));
+                line += 1;
+            }
+        }
+    }
+
+    pub fn grid_spacing(&self) -> f32 {
+        self.grid_spacing
+    }
+
+    /// Sets the distance between grid lines in graph space, which is kept to at least `MIN_GRID_SPACING`
+    /// Spacings that aren't finite are ignored
+    pub fn set_grid_spacing(&mut self, spacing: f32) {
+        if spacing.is_finite() {
+            self.grid_spacing = spacing.max(MIN_GRID_SPACING);
         }
     }
 
@@ -308,4 +2492,444 @@ impl<'a: 'b, 'b> NodeGraph<'a, 'b> {
         self.selector_panel_enabled = true;
         self
     }
+
+    pub fn enable_inspector_panel(mut self) -> Self {
+        self.inspector_panel_enabled = true;
+        self
+    }
+
+    pub fn enable_power_panel(mut self) -> Self {
+        self.power_panel_enabled = true;
+        self
+    }
+
+    pub fn enable_surplus_panel(mut self) -> Self {
+        self.surplus_panel_enabled = true;
+        self
+    }
+
+    pub fn enable_report_panel(mut self) -> Self {
+        self.report_panel_enabled = true;
+        self
+    }
+
+    /// Lets the report panel show what building the graph costs, by looking up the recipes of its buildings
+    pub fn with_recipe_database(mut self, database: Rc<RecipeDatabase>) -> Self {
+        self.recipe_database = Some(database);
+        self
+    }
+
+    /// Whether the project uses alternate recipes, to be given to the nodes that pick recipes
+    /// Changing the returned cell changes the setting, which is saved with the graph
+    pub fn alternate_recipes(&self) -> Rc<Cell<bool>> {
+        self.alternate_recipes.clone()
+    }
+
+    /// The materials needed to build the graph, see `ConstructionCost`
+    /// `None` without a recipe database
+    pub fn construction_cost(&self) -> Option<ConstructionCost> {
+        let database = self.recipe_database.as_ref()?;
+        Some(ConstructionCost::new(&self.report(), database, &self.construction_settings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node_input::NodeInput;
+    use crate::node_output::NodeOutput;
+    use crate::modules::ModuleDatabase;
+    use crate::nodes::graph_node::GraphNode;
+    use crate::nodes::machine_node::MachineNode;
+    use crate::nodes::one_to_n_node::OneToNNode;
+    use crate::nodes::sink_node::SinkNode;
+    use crate::nodes::source_node::SourceNode;
+    use crate::nodes::void_node::VoidNode;
+
+    /// A node with a `String` output, which doesn't fit the `u8` ports of the other test nodes
+    #[derive(Clone, Default)]
+    struct TextNode;
+
+    impl Node for TextNode {
+        fn title(&self) -> &str {
+            "Text"
+        }
+
+        fn body<'a>(&'a mut self) -> (Vec<NodeInput>, Box<dyn FnOnce(&mut Ui) + 'a>, Vec<NodeOutput>) {
+            (vec![], Box::new(|_| {}), vec![NodeOutput::output(String::new)])
+        }
+    }
+
+    /// A node counting how many times its body has run
+    #[derive(Clone, Default)]
+    struct CountingNode(Rc<Cell<usize>>);
+
+    impl Node for CountingNode {
+        fn title(&self) -> &str {
+            "Counting"
+        }
+
+        fn body<'a>(&'a mut self) -> (Vec<NodeInput>, Box<dyn FnOnce(&mut Ui) + 'a>, Vec<NodeOutput>) {
+            (vec![], Box::new(|_| self.0.set(self.0.get() + 1)), vec![])
+        }
+    }
+
+    fn test_graph() -> NodeGraph<'static, 'static> {
+        let mut graph = NodeGraph::new("test");
+        graph.register_node(SourceNode::default(), "Values", &[]);
+        graph.register_node(SinkNode::default(), "Values", &[]);
+        graph.register_node(TextNode, "Debug", &[]);
+        graph
+    }
+
+    /// Runs one frame of the graph without a window
+    fn show_frame(graph: &mut NodeGraph<'static, 'static>) {
+        let _ = egui::Context::default().run(egui::RawInput::default(), |ctx| graph.show(ctx));
+    }
+
+    /// Nodes serialized with only their type names, linked by [input node, input port, output node, output port]
+    fn serialized_nodes(type_names: &[&str], links: &[[usize; 4]]) -> SerializedNodes {
+        SerializedNodes {
+            nodes: type_names
+                .iter()
+                .enumerate()
+                .map(|(i, type_name)| SerializedNode {
+                    type_name: (*type_name).to_owned(),
+                    position: [i as f32 * 200.0, 0.0],
+                    data: String::new(),
+                    collapsed: false,
+                    body_size: None,
+                })
+                .collect(),
+            links: links
+                .iter()
+                .map(|[input, input_port, output, output_port]| SerializedLink {
+                    input: (*input, *input_port),
+                    output: (*output, *output_port),
+                    reroutes: Vec::new(),
+                    pipe_length: DEFAULT_PIPE_LENGTH,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn pasting_drops_links_that_do_not_fit() {
+        let mut graph = test_graph();
+        let (source, sink, text) = (std::any::type_name::<SourceNode>(), std::any::type_name::<SinkNode>(), std::any::type_name::<TextNode>());
+        let links = [
+            [1, 0, 0, 0],
+            // Into an input that is already linked
+            [1, 0, 0, 0],
+            // Into an input the sink doesn't have
+            [1, 3, 0, 0],
+            // From a String output into a u8 input
+            [3, 0, 2, 0],
+        ];
+        let text = serde_json::to_string(&serialized_nodes(&[source, sink, text, sink], &links)).unwrap();
+        graph.paste(&text, Pos2::ZERO).unwrap();
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(graph.links.len(), 1);
+        show_frame(&mut graph);
+    }
+
+    #[test]
+    fn surplus_is_what_linked_inputs_do_not_need() {
+        let mut keys = SlotMap::<NodeKey, ()>::with_key();
+        let [unlinked, partly_used, used_up, voided, into_tank, piped, consumer, void, tank] = [(); 9].map(|_| keys.insert(()));
+        let flow = |item: &str, per_minute| ItemFlow { item: item.to_owned(), per_minute };
+        let supplies = [unlinked, partly_used, used_up, voided, into_tank, piped].map(|node_key| ((node_key, 0), flow("ore", 90.0)));
+        let demands = vec![
+            ((consumer, 0), Demand::Flow(flow("ore", 60.0))),
+            ((consumer, 1), Demand::Flow(flow("ore", 30.0))),
+            ((consumer, 2), Demand::Flow(flow("ore", 90.0))),
+            ((consumer, 3), Demand::Flow(flow("coal", 90.0))),
+            ((consumer, 4), Demand::Flow(flow("ore", 90.0))),
+            ((void, 0), Demand::Excess),
+        ];
+        let mut links = SlotMap::<LinkKey, _>::with_key();
+        // The pipe from `piped` only carries 40/min of the 90/min its input needs
+        for (input, output, carried) in [
+            ((consumer, 0), partly_used, None),
+            ((consumer, 3), partly_used, None),
+            ((consumer, 2), used_up, None),
+            ((consumer, 1), voided, None),
+            ((void, 0), voided, None),
+            ((tank, 0), into_tank, None),
+            ((consumer, 4), piped, Some(40.0)),
+        ] {
+            links.insert((LinkInformation::new(input, (output, 0)), carried));
+        }
+        let summary = SurplusSummary::new(supplies.to_vec(), demands, links.values().map(|(link, carried)| (link, *carried)));
+        let surpluses: Vec<_> = summary.surpluses.iter().map(|surplus| (surplus.output.0, surplus.item.as_str(), surplus.per_minute, surplus.voided)).collect();
+        assert_eq!(
+            surpluses,
+            vec![(unlinked, "ore", 90.0, false), (partly_used, "ore", 30.0, false), (voided, "ore", 60.0, true), (piped, "ore", 50.0, false)],
+        );
+        // Inputs taking excess can't have surplus recycled into them
+        assert_eq!(summary.demands.len(), 5);
+    }
+
+    #[test]
+    fn loading_drops_links_to_ports_that_are_gone() {
+        let mut graph = test_graph();
+        // Machines only have ports for the recipe they craft, which isn't in this empty database
+        let machine = MachineNode::new(Rc::new(RecipeDatabase::default()), Rc::new(ModuleDatabase::default()), graph.alternate_recipes());
+        graph.register_node(machine, "Production", &[]);
+        let mut nodes = serialized_nodes(&[std::any::type_name::<MachineNode>(); 2], &[[1, 2, 0, 0]]);
+        for node in nodes.nodes.iter_mut() {
+            node.data = r#"{"recipe":"iron-gear-wheel","machine":null,"target":60.0,"clock_speed":1.0}"#.to_owned();
+        }
+        let saved = SerializedGraph { nodes, bookmarks: Vec::new(), comments: Vec::new(), alternate_recipes: false };
+        graph.load(&serde_json::to_string(&saved).unwrap()).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert!(graph.links.is_empty());
+        show_frame(&mut graph);
+    }
+
+    #[test]
+    fn loaded_bookmarks_keep_to_the_allowed_zoom() {
+        let mut graph = test_graph();
+        let bookmarks = [("Nowhere", 0.0), ("Inside out", -2.0), ("Too close", 100.0)]
+            .into_iter()
+            .map(|(name, scaling)| SerializedBookmark { name: name.to_owned(), translation: [10.0, 10.0], scaling })
+            .collect();
+        let saved = SerializedGraph { nodes: serialized_nodes(&[], &[]), bookmarks, comments: Vec::new(), alternate_recipes: false };
+        graph.load(&serde_json::to_string(&saved).unwrap()).unwrap();
+        for (name, scaling) in [("Nowhere", MIN_ZOOM), ("Inside out", MIN_ZOOM), ("Too close", MAX_ZOOM)] {
+            assert!(graph.go_to_bookmark(name));
+            assert_eq!(graph.transform.scaling, scaling);
+        }
+        show_frame(&mut graph);
+    }
+
+    #[test]
+    fn linking_an_input_again_replaces_its_link() {
+        let mut graph = test_graph();
+        let first = graph.add_node(Box::new(SourceNode::default()), Pos2::ZERO);
+        let second = graph.add_node(Box::new(SourceNode::default()), Pos2::new(0.0, 100.0));
+        let sink = graph.add_node(Box::new(SinkNode::default()), Pos2::new(200.0, 0.0));
+        // Keyboard linking uses the port types from the last frame
+        show_frame(&mut graph);
+        let port = |node_key, input| KeyboardFocus::Port { node_key, input, index: 0 };
+        graph.link_ports(port(first, false), port(sink, true));
+        graph.link_ports(port(sink, true), port(first, false));
+        assert_eq!(graph.links.len(), 1);
+        graph.link_ports(port(second, false), port(sink, true));
+        assert_eq!(graph.links.values().map(|link| link.output.0).collect::<Vec<_>>(), vec![second]);
+        show_frame(&mut graph);
+    }
+
+    #[test]
+    fn links_to_ports_a_new_recipe_changed_are_kept_until_they_fit_again() {
+        let database = RecipeDatabase::load(r#"{
+            "items": {"water": {"name": "water", "display_name": "Water", "kind": "Fluid", "stack_size": null}},
+            "recipes": {
+                "mine": {"name": "mine", "display_name": "Mine", "category": "crafting", "time": 1.0, "ingredients": [], "results": [{"item": "ore", "amount": 1.0}]},
+                "smelt": {"name": "smelt", "display_name": "Smelt", "category": "crafting", "time": 1.0, "ingredients": [{"item": "ore", "amount": 1.0}], "results": []},
+                "wash": {"name": "wash", "display_name": "Wash", "category": "crafting", "time": 1.0, "ingredients": [{"item": "water", "amount": 1.0}], "results": []}
+            },
+            "machines": {
+                "plant": {"name": "plant", "display_name": "Plant", "crafting_speed": 1.0, "categories": ["crafting"], "power": 0.0}
+            }
+        }"#).unwrap();
+        let mut graph = test_graph();
+        let machine = MachineNode::new(Rc::new(database), Rc::new(ModuleDatabase::default()), graph.alternate_recipes());
+        let with_recipe = |recipe: &str, target: f64| {
+            let mut machine = machine.clone();
+            machine.load(&format!(r#"{{"recipe":"{recipe}","machine":"plant","target":{target},"clock_speed":1.0}}"#));
+            Box::new(machine)
+        };
+        let mine = graph.add_node(with_recipe("mine", 90.0), Pos2::ZERO);
+        let emptied = graph.add_node(with_recipe("smelt", 60.0), Pos2::new(200.0, 0.0));
+        let retyped = graph.add_node(with_recipe("smelt", 60.0), Pos2::new(200.0, 100.0));
+        let kept = graph.add_node(with_recipe("smelt", 60.0), Pos2::new(200.0, 200.0));
+        for node_key in [emptied, retyped, kept] {
+            graph.links.insert(LinkInformation::new((node_key, 0), (mine, 0)));
+        }
+        show_frame(&mut graph);
+        assert_eq!(graph.links.len(), 3);
+        graph.nodes[emptied].node.load(r#"{"recipe":"mine","machine":"plant","target":60.0,"clock_speed":1.0}"#);
+        graph.nodes[retyped].node.load(r#"{"recipe":"wash","machine":"plant","target":60.0,"clock_speed":1.0}"#);
+        show_frame(&mut graph);
+        show_frame(&mut graph);
+        assert_eq!(graph.links.len(), 3);
+        // Broken links carry nothing, so what the kept link doesn't need is surplus
+        let surplus = graph.surplus_summary.surpluses.iter().find(|surplus| surplus.output == (mine, 0)).map(|surplus| surplus.per_minute);
+        assert_eq!(surplus, Some(30.0));
+        let broken_inputs = |graph: &NodeGraph| {
+            let mut inputs: Vec<_> = graph.broken_links.iter().map(|link_key| graph.links[*link_key].input.0).collect();
+            inputs.sort();
+            inputs
+        };
+        assert_eq!(broken_inputs(&graph), vec![emptied, retyped]);
+        // Going back to the old recipe brings the link back
+        graph.nodes[retyped].node.load(r#"{"recipe":"smelt","machine":"plant","target":60.0,"clock_speed":1.0}"#);
+        show_frame(&mut graph);
+        assert_eq!(broken_inputs(&graph), vec![emptied]);
+    }
+
+    #[test]
+    fn links_from_every_output_of_a_one_to_n_are_loaded() {
+        let mut graph = test_graph();
+        graph.register_node(OneToNNode::default(), "Logistics", &[]);
+        let source = graph.add_node(Box::new(SourceNode::default()), Pos2::ZERO);
+        let split = graph.add_node(Box::new(OneToNNode::default()), Pos2::new(200.0, 0.0));
+        graph.links.insert(LinkInformation::new((split, 0), (source, 0)));
+        // Each linked output adds another one to the node in the next frame
+        for index in 0..3 {
+            show_frame(&mut graph);
+            let sink = graph.add_node(Box::new(SinkNode::default()), Pos2::new(400.0, index as f32 * 100.0));
+            graph.links.insert(LinkInformation::new((sink, 0), (split, index)));
+        }
+        show_frame(&mut graph);
+        assert_eq!(graph.links.len(), 4);
+        let mut loaded = test_graph();
+        loaded.register_node(OneToNNode::default(), "Logistics", &[]);
+        loaded.load(&graph.save()).unwrap();
+        assert_eq!(loaded.links.len(), 4);
+        show_frame(&mut loaded);
+        assert_eq!(loaded.links.len(), 4);
+    }
+
+    /// A database where mining makes ore and smelting uses it
+    fn ore_database() -> RecipeDatabase {
+        RecipeDatabase::load(r#"{
+            "items": {},
+            "recipes": {
+                "mine": {"name": "mine", "display_name": "Mine", "category": "crafting", "time": 1.0, "ingredients": [], "results": [{"item": "ore", "amount": 1.0}]},
+                "smelt": {"name": "smelt", "display_name": "Smelt", "category": "crafting", "time": 1.0, "ingredients": [{"item": "ore", "amount": 1.0}], "results": []}
+            },
+            "machines": {
+                "plant": {"name": "plant", "display_name": "Plant", "crafting_speed": 1.0, "categories": ["crafting"], "power": 0.0}
+            }
+        }"#).unwrap()
+    }
+
+    /// A machine crafting `recipe` `target` times a minute
+    fn machine(graph: &mut NodeGraph<'static, 'static>, recipe: &str, target: f64, position: Pos2) -> NodeKey {
+        let mut machine = MachineNode::new(Rc::new(ore_database()), Rc::new(ModuleDatabase::default()), graph.alternate_recipes());
+        machine.load(&format!(r#"{{"recipe":"{recipe}","machine":"plant","target":{target},"clock_speed":1.0}}"#));
+        graph.add_node(Box::new(machine), position)
+    }
+
+    #[test]
+    fn modules_change_what_a_fixed_number_of_machines_make() {
+        let database = RecipeDatabase::load(r#"{
+            "items": {},
+            "recipes": {
+                "mine": {"name": "mine", "display_name": "Mine", "category": "crafting", "time": 1.0, "ingredients": [], "results": [{"item": "ore", "amount": 1.0}]}
+            },
+            "machines": {
+                "plant": {"name": "plant", "display_name": "Plant", "crafting_speed": 1.0, "categories": ["crafting"], "power": 0.0, "module_slots": 2}
+            }
+        }"#).unwrap();
+        let modules = ModuleDatabase::load(r#"{"modules": {
+            "productivity": {"name": "productivity", "display_name": "Productivity", "effect": {"productivity": 0.5}},
+            "speed": {"name": "speed", "display_name": "Speed", "effect": {"speed": 0.5}}
+        }}"#).unwrap();
+        let mut machine = MachineNode::new(Rc::new(database), Rc::new(modules), Rc::default());
+        let mut emitted = |data: &str| {
+            machine.load(data);
+            let (_, _, outputs) = machine.body();
+            let flow = (outputs[0].output_callback)().downcast::<ItemFlow>().unwrap();
+            flow.per_minute
+        };
+        // Two machines crafting once a second
+        assert_eq!(emitted(r#"{"recipe":"mine","machine":"plant","target":60.0,"machines":2.0,"clock_speed":1.0}"#), 120.0);
+        assert_eq!(emitted(r#"{"recipe":"mine","machine":"plant","target":60.0,"machines":2.0,"clock_speed":1.0,"modules":["productivity"]}"#), 180.0);
+        assert_eq!(emitted(r#"{"recipe":"mine","machine":"plant","target":60.0,"machines":2.0,"clock_speed":1.0,"modules":["speed"]}"#), 180.0);
+        assert_eq!(emitted(r#"{"recipe":"mine","machine":"plant","target":60.0,"machines":2.0,"clock_speed":0.5,"modules":["speed", "productivity"]}"#), 135.0);
+        // Working towards a target, modules take fewer machines for the same output
+        assert_eq!(emitted(r#"{"recipe":"mine","machine":"plant","target":60.0,"clock_speed":1.0,"modules":["productivity"]}"#), 60.0);
+    }
+
+    #[test]
+    fn outputs_linked_more_than_once_split_what_they_make() {
+        let mut graph = test_graph();
+        let mine = machine(&mut graph, "mine", 90.0, Pos2::ZERO);
+        let first = machine(&mut graph, "smelt", 60.0, Pos2::new(200.0, 0.0));
+        let second = machine(&mut graph, "smelt", 60.0, Pos2::new(200.0, 100.0));
+        for node_key in [first, second] {
+            graph.links.insert(LinkInformation::new((node_key, 0), (mine, 0)));
+        }
+        // Demands come from the ports shown in the frame before, and machines check what they were given in the frame before that
+        for _ in 0..3 {
+            show_frame(&mut graph);
+        }
+        assert!(graph.nodes[first].node.errors().is_empty());
+        assert_eq!(graph.nodes[second].node.errors(), vec!["Only 30/min of the 60/min of ore needed is supplied".to_owned()]);
+    }
+
+    #[test]
+    fn voiding_a_linked_output_keeps_its_link() {
+        let mut graph = test_graph();
+        graph.register_node(VoidNode::new(Rc::default()), "Production", &[]);
+        let mine = machine(&mut graph, "mine", 90.0, Pos2::ZERO);
+        let smelt = machine(&mut graph, "smelt", 60.0, Pos2::new(200.0, 0.0));
+        graph.links.insert(LinkInformation::new((smelt, 0), (mine, 0)));
+        // Surplus is worked out from the ports shown in the frame before
+        show_frame(&mut graph);
+        show_frame(&mut graph);
+        assert_eq!(graph.surplus_summary.surpluses.len(), 1);
+        graph.void_surplus((mine, 0));
+        assert_eq!(graph.links.len(), 2);
+        show_frame(&mut graph);
+        show_frame(&mut graph);
+        assert_eq!(graph.links.len(), 2);
+        assert!(graph.nodes[smelt].node.errors().is_empty());
+        assert!(graph.surplus_summary.surpluses.iter().all(|surplus| surplus.voided));
+    }
+
+    #[test]
+    fn reports_count_links_of_graphs_that_were_never_shown() {
+        let mut graph = test_graph();
+        let mine = machine(&mut graph, "mine", 60.0, Pos2::ZERO);
+        let smelt = machine(&mut graph, "smelt", 60.0, Pos2::new(200.0, 0.0));
+        graph.links.insert(LinkInformation::new((smelt, 0), (mine, 0)));
+        let report = graph.report();
+        assert_eq!((report.belt_links, report.pipes), (1, 0));
+    }
+
+    #[test]
+    fn alternate_recipes_are_saved_with_the_graph() {
+        let graph = test_graph();
+        graph.alternate_recipes().set(true);
+        let saved = graph.save();
+        let mut loaded = test_graph();
+        let shared = loaded.alternate_recipes();
+        loaded.load(&saved).unwrap();
+        assert!(shared.get());
+        loaded.load(&test_graph().save()).unwrap();
+        assert!(!shared.get());
+    }
+
+    #[test]
+    fn culled_graph_nodes_keep_showing_their_graph() {
+        let runs = Rc::new(Cell::new(0));
+        let mut nested = NodeGraph::new("nested");
+        nested.add_node(Box::new(CountingNode(runs.clone())), Pos2::ZERO);
+        let mut graph = test_graph();
+        let graph_node = graph.add_node(Box::new(GraphNode::from(nested)), Pos2::ZERO);
+        show_frame(&mut graph);
+        assert_eq!(runs.get(), 1);
+        // Nodes are culled once they have been shown and are outside the viewport
+        graph.nodes[graph_node].position = Pos2::new(100_000.0, 100_000.0);
+        show_frame(&mut graph);
+        show_frame(&mut graph);
+        assert_eq!(runs.get(), 3);
+    }
+
+    #[test]
+    fn grid_spacing_stays_positive() {
+        let mut graph = test_graph();
+        graph.set_grid_spacing(0.0);
+        assert_eq!(graph.grid_spacing(), MIN_GRID_SPACING);
+        graph.set_grid_spacing(-20.0);
+        assert_eq!(graph.grid_spacing(), MIN_GRID_SPACING);
+        graph.set_grid_spacing(f32::NAN);
+        assert_eq!(graph.grid_spacing(), MIN_GRID_SPACING);
+        graph.set_grid_spacing(32.0);
+        assert_eq!(graph.grid_spacing(), 32.0);
+    }
 }
@@ -8,10 +8,13 @@ use eframe::egui::Ui;
 /// The input callback will be given the value propogated from new connections
 /// A node without an input callback cannot connect to any input nodes
 /// Input nodes can only connect to output nodes of the same type
+/// A value of another type given to the input callback is ignored
 pub struct NodeInput<'a, 'b> {
     pub ui_callback: Box<dyn FnOnce(&mut Ui) + 'a>,
     pub input_callback: Box<dyn FnOnce(Box<dyn Any>) + 'b>,
     pub input_type: TypeId,
+    /// The name of the type passed through the input, used to describe the port to screen readers
+    pub type_name: &'static str,
 }
 
 /// Unique internal type to prevent input callbackless nodes from connecting
@@ -26,8 +29,13 @@ impl<'a, 'b> NodeInput<'a, 'b> {
     ) -> Self {
         Self {
             ui_callback: Box::new(ui_callback),
-            input_callback: Box::new(|x| input_callback(*x.downcast::<T>().unwrap())),
+            input_callback: Box::new(|x| {
+                if let Ok(x) = x.downcast::<T>() {
+                    input_callback(*x)
+                }
+            }),
             input_type: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
         }
     }
 
@@ -37,6 +45,7 @@ impl<'a, 'b> NodeInput<'a, 'b> {
             ui_callback: Box::new(ui_callback),
             input_callback: Box::new(|_| {}),
             input_type: TypeId::of::<EmptyNodeInput>(),
+            type_name: "none",
         }
     }
 
@@ -44,8 +53,13 @@ impl<'a, 'b> NodeInput<'a, 'b> {
     pub fn input<T: 'static>(input_callback: impl FnOnce(T) + 'b) -> Self {
         Self {
             ui_callback: Box::new(|_| {}),
-            input_callback: Box::new(|x| input_callback(*x.downcast::<T>().unwrap())),
+            input_callback: Box::new(|x| {
+                if let Ok(x) = x.downcast::<T>() {
+                    input_callback(*x)
+                }
+            }),
             input_type: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
         }
     }
 
@@ -55,6 +69,7 @@ impl<'a, 'b> NodeInput<'a, 'b> {
             ui_callback: Box::new(|_| {}),
             input_callback: Box::new(|_| {}),
             input_type: TypeId::of::<EmptyNodeInput>(),
+            type_name: "none",
         }
     }
 }
@@ -6,12 +6,15 @@ use eframe::egui::Ui;
 /// Can optionally be created with a ui callback and output callback
 /// The ui callback will be shown to the left of the node
 /// The output callback will give the value to be propogated along new connections
+/// It is called once for each connection, so an output can be connected to several inputs
 /// A node without an output callback cannot connect to any input nodes
 /// Output nodes can only connect to input nodes of the same type
 pub struct NodeOutput<'a, 'b> {
     pub ui_callback: Box<dyn FnOnce(&mut Ui) + 'a>,
-    pub output_callback: Box<dyn FnOnce() -> Box<dyn Any> + 'b>,
+    pub output_callback: Box<dyn Fn() -> Box<dyn Any> + 'b>,
     pub output_type: TypeId,
+    /// The name of the type passed through the output, used to describe the port to screen readers
+    pub type_name: &'static str,
 }
 
 /// Unique internal type to prevent output callbackless nodes from connecting
@@ -22,12 +25,13 @@ impl<'a, 'b> NodeOutput<'a, 'b> {
     /// Create a new NodeOutput, with both a ui and output callback
     pub fn new<T: 'static>(
         ui_callback: impl FnOnce(&mut Ui) + 'a,
-        output_callback: impl FnOnce() -> T + 'b,
+        output_callback: impl Fn() -> T + 'b,
     ) -> Self {
         Self {
             ui_callback: Box::new(ui_callback),
-            output_callback: Box::new(|| Box::new(output_callback())),
+            output_callback: Box::new(move || Box::new(output_callback())),
             output_type: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
         }
     }
 
@@ -37,15 +41,17 @@ impl<'a, 'b> NodeOutput<'a, 'b> {
             ui_callback: Box::new(ui_callback),
             output_callback: Box::new(|| Box::new(EmptyNodeOutput {})),
             output_type: TypeId::of::<EmptyNodeOutput>(),
+            type_name: "none",
         }
     }
 
     /// Create a new NodeOutput with only an output callback
-    pub fn output<T: 'static>(output_callback: impl FnOnce() -> T + 'b) -> Self {
+    pub fn output<T: 'static>(output_callback: impl Fn() -> T + 'b) -> Self {
         Self {
             ui_callback: Box::new(|_| {}),
-            output_callback: Box::new(|| Box::new(output_callback())),
+            output_callback: Box::new(move || Box::new(output_callback())),
             output_type: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
         }
     }
 
@@ -55,6 +61,7 @@ impl<'a, 'b> NodeOutput<'a, 'b> {
             ui_callback: Box::new(|_| {}),
             output_callback: Box::new(|| Box::new(EmptyNodeOutput {})),
             output_type: TypeId::of::<EmptyNodeOutput>(),
+            type_name: "none",
         }
     }
 }
@@ -1,7 +1,9 @@
 use std::cell::RefCell;
 
+use eframe::egui::Color32;
 use eframe::egui::Ui;
 
+use crate::theme::NodeStyle;
 use crate::unselectable_label;
 use crate::{node_input::NodeInput, node_output::NodeOutput, Node};
 
@@ -16,6 +18,14 @@ impl Node for AdderNode {
         "Adder"
     }
 
+    fn style(&self) -> NodeStyle {
+        NodeStyle {
+            header_color: Some(Color32::from_rgb(60, 110, 200)),
+            icon: Some("➕".to_owned()),
+            ..Default::default()
+        }
+    }
+
     fn body<'a>(
         &'a mut self,
     ) -> (
@@ -47,4 +57,12 @@ impl Node for AdderNode {
             })],
         )
     }
+
+    fn inspector(&mut self, ui: &mut Ui) {
+        let (value_1, value_2) = (*self.value_1.borrow(), *self.value_2.borrow());
+        ui.label(format!("{value_1} + {value_2} = {}", value_1.wrapping_add(value_2)));
+        if value_1.checked_add(value_2).is_none() {
+            ui.weak("The sum wrapped around, as it doesn't fit in a u8");
+        }
+    }
 }
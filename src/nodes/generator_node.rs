@@ -0,0 +1,174 @@
+use std::cell::RefCell;
+
+use eframe::egui;
+use eframe::egui::Color32;
+use eframe::egui::Ui;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::flow::format_power;
+use crate::report::NodeReport;
+use crate::theme::NodeStyle;
+use crate::unselectable_label;
+use crate::{node_input::NodeInput, node_output::NodeOutput, Node};
+
+/// What kind of generator a `GeneratorNode` is made of
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum GeneratorKind {
+    #[default]
+    SteamEngine,
+    SolarPanel,
+    /// Any other generator, with its power set by hand
+    Custom,
+}
+
+impl GeneratorKind {
+    const ALL: [GeneratorKind; 3] = [GeneratorKind::SteamEngine, GeneratorKind::SolarPanel, GeneratorKind::Custom];
+
+    fn name(self) -> &'static str {
+        match self {
+            GeneratorKind::SteamEngine => "Steam engine",
+            GeneratorKind::SolarPanel => "Solar panel",
+            GeneratorKind::Custom => "Custom",
+        }
+    }
+
+    /// The name of the generators in reports, where custom ones are just generators
+    fn building_name(self) -> &'static str {
+        match self {
+            GeneratorKind::Custom => "Generator",
+            kind => kind.name(),
+        }
+    }
+}
+
+/// The power of a steam engine running on enough steam, in watts
+const STEAM_ENGINE_POWER: f64 = 900e3;
+/// The power of a solar panel in full daylight, in watts
+const SOLAR_PANEL_PEAK_POWER: f64 = 60e3;
+/// How much of its peak power a solar panel gives on average over a day and night
+const SOLAR_PANEL_DAY_FACTOR: f64 = 0.7;
+/// The accumulators needed to carry one solar panel's share of the load through the night
+const ACCUMULATORS_PER_SOLAR_PANEL: f64 = 0.84;
+
+/// A number of generators of one kind, supplying power to the graph
+#[derive(Clone)]
+pub struct GeneratorNode {
+    kind: RefCell<GeneratorKind>,
+    count: RefCell<u32>,
+    /// The power of each custom generator, in megawatts
+    custom_power: RefCell<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedGenerator {
+    kind: GeneratorKind,
+    count: u32,
+    custom_power: f64,
+}
+
+impl Default for GeneratorNode {
+    fn default() -> Self {
+        Self {
+            kind: RefCell::new(GeneratorKind::default()),
+            count: RefCell::new(1),
+            custom_power: RefCell::new(1.0),
+        }
+    }
+}
+
+impl GeneratorNode {
+    /// The average power of one generator, in watts
+    /// Solar panels are averaged over a whole day, as accumulators carry their power through the night
+    fn power_per_generator(&self) -> f64 {
+        match *self.kind.borrow() {
+            GeneratorKind::SteamEngine => STEAM_ENGINE_POWER,
+            GeneratorKind::SolarPanel => SOLAR_PANEL_PEAK_POWER * SOLAR_PANEL_DAY_FACTOR,
+            GeneratorKind::Custom => *self.custom_power.borrow() * 1e6,
+        }
+    }
+
+    fn settings_ui(&self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            for kind in GeneratorKind::ALL {
+                ui.selectable_value(&mut *self.kind.borrow_mut(), kind, kind.name());
+            }
+        });
+        ui.horizontal(|ui| {
+            unselectable_label(ui, "Count");
+            ui.add(egui::DragValue::new(&mut *self.count.borrow_mut()));
+        });
+        if *self.kind.borrow() == GeneratorKind::Custom {
+            ui.horizontal(|ui| {
+                unselectable_label(ui, "Each");
+                ui.add(egui::DragValue::new(&mut *self.custom_power.borrow_mut()).range(0.0..=f64::MAX).suffix(" MW"));
+            });
+        }
+    }
+}
+
+impl Node for GeneratorNode {
+    fn title(&self) -> &str {
+        "Generator"
+    }
+
+    fn style(&self) -> NodeStyle {
+        NodeStyle {
+            header_color: Some(Color32::from_rgb(210, 180, 40)),
+            icon: Some("⚡".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn body<'a>(
+        &'a mut self,
+    ) -> (std::vec::Vec<NodeInput>, Box<(dyn FnOnce(&mut Ui) + 'a)>, std::vec::Vec<NodeOutput>) {
+        (vec![], Box::new(|ui| {
+            self.settings_ui(ui);
+            unselectable_label(ui, format!("Supplies {}", format_power(self.power_supply())));
+        }), vec![])
+    }
+
+    fn inspector(&mut self, ui: &mut Ui) {
+        self.settings_ui(ui);
+        let count = *self.count.borrow() as f64;
+        ui.label(format!("{} each, {} in total", format_power(self.power_per_generator()), format_power(self.power_supply())));
+        if *self.kind.borrow() == GeneratorKind::SolarPanel {
+            ui.label(format!("{} at peak, in full daylight", format_power(SOLAR_PANEL_PEAK_POWER * count)));
+            ui.label(format!("{} accumulators to last the night", (ACCUMULATORS_PER_SOLAR_PANEL * count).ceil()));
+        }
+    }
+
+    fn power_supply(&self) -> f64 {
+        self.power_per_generator() * *self.count.borrow() as f64
+    }
+
+    fn report(&self) -> NodeReport {
+        let count = *self.count.borrow() as f64;
+        let mut buildings = vec![(self.kind.borrow().building_name().to_owned(), count)];
+        if *self.kind.borrow() == GeneratorKind::SolarPanel {
+            buildings.push(("Accumulator".to_owned(), ACCUMULATORS_PER_SOLAR_PANEL * count));
+        }
+        NodeReport {
+            buildings,
+            ..Default::default()
+        }
+    }
+
+    fn save(&self) -> String {
+        serde_json::to_string(&SavedGenerator {
+            kind: *self.kind.borrow(),
+            count: *self.count.borrow(),
+            custom_power: *self.custom_power.borrow(),
+        })
+        .expect("serializing a generator can't fail")
+    }
+
+    fn load(&mut self, data: &str) {
+        if let Ok(saved) = serde_json::from_str::<SavedGenerator>(data) {
+            self.kind.replace(saved.kind);
+            self.count.replace(saved.count);
+            self.custom_power.replace(saved.custom_power);
+        }
+    }
+}
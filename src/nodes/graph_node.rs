@@ -1,7 +1,10 @@
 use std::cell::RefCell;
 
+use eframe::egui::Color32;
 use eframe::egui::Ui;
 
+use crate::report::NodeReport;
+use crate::theme::NodeStyle;
 use crate::{createable_node::CreatableNode, node_graph::NodeGraph, node_input::NodeInput, node_output::NodeOutput, Node};
 
 #[derive(Clone)]
@@ -16,11 +19,26 @@ impl<'a: 'b, 'b: 'c, 'c> CreatableNode<'c> for GraphNode<'a, 'b> {
     }
 }
 
+/// Wraps an existing graph, keeping the nodes it has registered
+impl<'a, 'b> From<NodeGraph<'a, 'b>> for GraphNode<'a, 'b> {
+    fn from(graph: NodeGraph<'a, 'b>) -> Self {
+        Self { graph: graph.into() }
+    }
+}
+
 impl<'a: 'b, 'b> Node for GraphNode<'a, 'b> {
     fn title(&self) -> &str {
         "Graph Node"
     }
 
+    fn style(&self) -> NodeStyle {
+        NodeStyle {
+            header_color: Some(Color32::from_rgb(140, 80, 190)),
+            icon: Some("📂".to_owned()),
+            ..Default::default()
+        }
+    }
+
     fn body<'c>(
         &'c mut self,
     ) -> (std::vec::Vec<NodeInput>, Box<(dyn FnOnce(&mut Ui) + 'c)>, std::vec::Vec<NodeOutput>) { 
@@ -28,4 +46,28 @@ impl<'a: 'b, 'b> Node for GraphNode<'a, 'b> {
             self.graph.borrow_mut().show_inside(ui)
         }), vec![])
     }
+
+    fn save(&self) -> String {
+        self.graph.borrow().save()
+    }
+
+    fn load(&mut self, data: &str) {
+        let _ = self.graph.borrow_mut().load(data);
+    }
+
+    fn resizable(&self) -> bool {
+        true
+    }
+
+    fn power_draw(&self) -> f64 {
+        self.graph.borrow().power_draw()
+    }
+
+    fn power_supply(&self) -> f64 {
+        self.graph.borrow().power_supply()
+    }
+
+    fn report(&self) -> NodeReport {
+        self.graph.borrow().node_report()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,489 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use eframe::egui;
+use eframe::egui::Color32;
+use eframe::egui::RichText;
+use eframe::egui::Ui;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::flow::format_power;
+use crate::flow::format_rate;
+use crate::flow::Demand;
+use crate::flow::FluidFlow;
+use crate::flow::ItemFlow;
+use crate::modules::Effect;
+use crate::modules::Module;
+use crate::modules::ModuleDatabase;
+use crate::recipe_database::ItemKind;
+use crate::recipe_database::Machine;
+use crate::recipe_database::Recipe;
+use crate::recipe_database::RecipeDatabase;
+use crate::report::NodeReport;
+use crate::theme::NodeStyle;
+use crate::unselectable_label;
+use crate::{node_input::NodeInput, node_output::NodeOutput, Node};
+
+/// Runs a recipe from the recipe database at a target rate, working out how many machines that takes,
+/// or runs a fixed number of machines, working out what they make
+/// Has an input for each ingredient and an output for each result, passing `ItemFlow`s, or `FluidFlow`s for fluids
+/// Modules in the machines and beacons around them change the speed, productivity and energy use of every machine
+#[derive(Clone)]
+pub struct MachineNode {
+    database: Rc<RecipeDatabase>,
+    module_database: Rc<ModuleDatabase>,
+    recipe: RefCell<Option<String>>,
+    machine: RefCell<Option<String>>,
+    /// The wanted output of the recipe's first result, per minute
+    target: RefCell<f64>,
+    /// The number of machines to run instead of working towards the target, when set
+    machines: RefCell<Option<f64>>,
+    /// 1 is the normal speed, machines can be underclocked down to 0.01 and overclocked up to 2.5
+    clock_speed: RefCell<f64>,
+    /// The module in each slot of every machine, empty strings for empty slots
+    modules: RefCell<Vec<String>>,
+    /// The number of beacons in range of every machine
+    beacons: RefCell<u32>,
+    /// The module in each slot of every beacon
+    beacon_modules: RefCell<Vec<String>>,
+    /// The flows linked into each ingredient input during the last frame
+    supplied: RefCell<Vec<Option<ItemFlow>>>,
+    /// The flows linked into each ingredient input so far this frame
+    received: RefCell<Vec<Option<ItemFlow>>>,
+    /// Whether the project uses alternate recipes, shared with the graph it is saved with
+    alternate_recipes: Rc<Cell<bool>>,
+    search: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedMachine {
+    recipe: Option<String>,
+    machine: Option<String>,
+    target: f64,
+    #[serde(default)]
+    machines: Option<f64>,
+    clock_speed: f64,
+    #[serde(default)]
+    modules: Vec<String>,
+    #[serde(default)]
+    beacons: u32,
+    #[serde(default)]
+    beacon_modules: Vec<String>,
+}
+
+impl MachineNode {
+    /// `alternate_recipes` is the project's setting, usually from `NodeGraph::alternate_recipes`
+    pub fn new(database: Rc<RecipeDatabase>, module_database: Rc<ModuleDatabase>, alternate_recipes: Rc<Cell<bool>>) -> Self {
+        Self {
+            database,
+            module_database,
+            recipe: RefCell::new(None),
+            machine: RefCell::new(None),
+            target: RefCell::new(60.0),
+            machines: RefCell::new(None),
+            clock_speed: RefCell::new(1.0),
+            modules: RefCell::new(Vec::new()),
+            beacons: RefCell::new(0),
+            beacon_modules: RefCell::new(Vec::new()),
+            supplied: RefCell::new(Vec::new()),
+            received: RefCell::new(Vec::new()),
+            alternate_recipes,
+            search: String::new(),
+        }
+    }
+
+    fn recipe(&self) -> Option<&Recipe> {
+        self.recipe.borrow().as_ref().and_then(|name| self.database.recipes.get(name))
+    }
+
+    fn machine(&self) -> Option<&Machine> {
+        self.machine.borrow().as_ref().and_then(|name| self.database.machines.get(name))
+    }
+
+    fn display_name<'a>(&'a self, item: &'a str) -> &'a str {
+        self.database.display_name(item)
+    }
+
+    /// The combined effect of the modules in the machines and beacons
+    /// Machines only use as many modules as they have slots
+    fn effect(&self) -> Effect {
+        let (Some(recipe), Some(machine)) = (self.recipe(), self.machine()) else {
+            return Effect::default();
+        };
+        let modules = self.modules.borrow();
+        let slots = modules.len().min(machine.module_slots as usize);
+        self.module_database.effect(&recipe.name, &modules[..slots], *self.beacons.borrow(), &self.beacon_modules.borrow())
+    }
+
+    fn is_fluid(&self, item: &str) -> bool {
+        self.database.items.get(item).is_some_and(|item| item.kind == ItemKind::Fluid)
+    }
+
+    /// How many times the recipe is crafted per minute, by the fixed number of machines or to reach the target
+    /// Productivity makes each craft give more, so a fixed number of machines makes more with it,
+    /// while reaching the target takes fewer crafts
+    fn crafts_per_minute(&self) -> f64 {
+        if let Some(machines) = *self.machines.borrow() {
+            return machines * self.crafts_per_machine().unwrap_or(0.0);
+        }
+        let main_amount = self.recipe().and_then(|recipe| recipe.results.first()).map_or(1.0, |result| result.amount);
+        *self.target.borrow() / (main_amount * (1.0 + self.effect().productivity))
+    }
+
+    /// How many times one machine crafts the recipe per minute, at the current clock speed
+    fn crafts_per_machine(&self) -> Option<f64> {
+        let (recipe, machine) = (self.recipe()?, self.machine()?);
+        Some(60.0 / recipe.time * machine.crafting_speed * *self.clock_speed.borrow() * (1.0 + self.effect().speed))
+    }
+
+    /// The number of machines needed to reach the target, which usually isn't a whole number, or the fixed number of machines
+    fn machine_count(&self) -> Option<f64> {
+        let crafts_per_machine = self.crafts_per_machine()?;
+        Some(self.machines.borrow().unwrap_or_else(|| self.crafts_per_minute() / crafts_per_machine))
+    }
+
+    /// Switches between running a fixed number of machines and working towards the target,
+    /// starting from what the node does now so its flows stay the same
+    fn set_fixed_machines(&self, fixed: bool) {
+        if fixed {
+            self.machines.replace(Some(self.machine_count().unwrap_or(1.0)));
+        } else {
+            let main_amount = self.recipe().and_then(|recipe| recipe.results.first()).map_or(1.0, |result| result.amount);
+            let target = main_amount * (1.0 + self.effect().productivity) * self.crafts_per_minute();
+            self.machines.replace(None);
+            self.target.replace(target);
+        }
+    }
+
+    /// Edits the target, or the number of machines when it is fixed
+    fn rate_ui(&self, ui: &mut Ui) {
+        let machines = *self.machines.borrow();
+        if let Some(mut machines) = machines {
+            if ui.add(egui::DragValue::new(&mut machines).range(0.0..=f64::MAX).max_decimals(2)).changed() {
+                self.machines.replace(Some(machines));
+            }
+        } else {
+            let mut target = *self.target.borrow();
+            if ui.add(egui::DragValue::new(&mut target).range(0.0..=f64::MAX).suffix("/min").max_decimals(2)).changed() {
+                self.target.replace(target);
+            }
+        }
+    }
+
+    /// The power drawn by one machine at the current clock speed, in watts
+    fn power_per_machine(&self) -> Option<f64> {
+        let machine = self.machine()?;
+        Some(machine.power * self.clock_speed.borrow().powf(machine.power_exponent) * (1.0 + self.effect().consumption))
+    }
+
+    /// The average power drawn by all the machines, in watts
+    /// The last machine only runs part of the time when the machine count isn't a whole number
+    fn power(&self) -> Option<f64> {
+        Some(self.power_per_machine()? * self.machine_count()?)
+    }
+
+    fn set_recipe(&self, name: &str) {
+        self.recipe.replace(Some(name.to_owned()));
+        let category = &self.database.recipes[name].category;
+        if !self.machine().is_some_and(|machine| machine.categories.contains(category)) {
+            let machine = self.database.machines.values().find(|machine| machine.categories.contains(category));
+            self.machine.replace(machine.map(|machine| machine.name.clone()));
+        }
+    }
+
+    /// The supply of an ingredient compared to what is needed, `None` while nothing is linked to it
+    fn supply_problem(&self, index: usize) -> Option<String> {
+        let recipe = self.recipe()?;
+        let ingredient = recipe.ingredients.get(index)?;
+        let supplied = self.supplied.borrow();
+        let flow = supplied.get(index)?.as_ref()?;
+        let needed = ingredient.amount * self.crafts_per_minute();
+        // Empty tanks and pumps pass on a flow of nothing
+        if flow.item != ingredient.item && !flow.item.is_empty() {
+            Some(format!("{} is linked to the {} input", self.display_name(&flow.item), self.display_name(&ingredient.item)))
+        } else if flow.per_minute + 1e-9 < needed {
+            Some(format!(
+                "Only {}/min of the {}/min of {} needed is supplied",
+                format_rate(flow.per_minute),
+                format_rate(needed),
+                self.display_name(&ingredient.item),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Picks the module for each of `slots` slots, offering the modules `allowed` accepts
+    fn module_slots_ui(&self, ui: &mut Ui, label: &str, modules: &RefCell<Vec<String>>, slots: u32, allowed: impl Fn(&Module) -> bool) {
+        let mut modules = modules.borrow_mut();
+        let len = modules.len().max(slots as usize);
+        modules.resize(len, String::new());
+        for (i, slot) in modules.iter_mut().take(slots as usize).enumerate() {
+            let selected = self.module_database.modules.get(slot).map_or("Empty", |module| module.display_name.as_str());
+            egui::ComboBox::from_id_source((label, i)).selected_text(selected).show_ui(ui, |ui| {
+                ui.selectable_value(slot, String::new(), "Empty");
+                for module in self.module_database.modules.values().filter(|module| allowed(module)) {
+                    ui.selectable_value(slot, module.name.clone(), &module.display_name);
+                }
+            });
+        }
+    }
+
+    fn clock_speed_ui(&self, ui: &mut Ui) {
+        let mut percent = *self.clock_speed.borrow() * 100.0;
+        if ui.add(egui::DragValue::new(&mut percent).range(1.0..=250.0).suffix("%").max_decimals(1)).changed() {
+            self.clock_speed.replace(percent / 100.0);
+        }
+    }
+}
+
+impl Node for MachineNode {
+    fn title(&self) -> &str {
+        self.recipe().map_or("Machine", |recipe| recipe.display_name.as_str())
+    }
+
+    fn style(&self) -> NodeStyle {
+        NodeStyle {
+            header_color: Some(Color32::from_rgb(200, 120, 50)),
+            icon: Some("🏭".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn body<'a>(
+        &'a mut self,
+    ) -> (std::vec::Vec<NodeInput>, Box<(dyn FnOnce(&mut Ui) + 'a)>, std::vec::Vec<NodeOutput>) {
+        let this = &*self;
+        let Some(recipe) = this.recipe() else {
+            return (vec![], Box::new(|ui| {
+                unselectable_label(ui, RichText::new("Pick a recipe in the inspector").weak());
+            }), vec![]);
+        };
+        let received = this.received.take();
+        this.supplied.replace(received);
+        this.supplied.borrow_mut().resize(recipe.ingredients.len(), None);
+        this.received.borrow_mut().resize(recipe.ingredients.len(), None);
+        let crafts_per_minute = this.crafts_per_minute();
+        let effect = this.effect();
+        let inputs = recipe.ingredients.iter().enumerate().map(|(i, ingredient)| {
+            let ui_callback = move |ui: &mut Ui| {
+                let text = format!("{} {}/min", this.display_name(&ingredient.item), format_rate(ingredient.amount * crafts_per_minute));
+                if this.supply_problem(i).is_some() {
+                    unselectable_label(ui, RichText::new(text).color(ui.visuals().error_fg_color));
+                } else {
+                    unselectable_label(ui, text);
+                }
+            };
+            // Fluids are supplied through fluid ports, and kept as item flows like everything else
+            if this.is_fluid(&ingredient.item) {
+                NodeInput::new(ui_callback, move |flow: FluidFlow| {
+                    this.received.borrow_mut()[i] = Some(ItemFlow { item: flow.fluid, per_minute: flow.per_minute });
+                })
+            } else {
+                NodeInput::new(ui_callback, move |flow: ItemFlow| {
+                    this.received.borrow_mut()[i] = Some(flow);
+                })
+            }
+        }).collect();
+        let outputs = recipe.results.iter().map(|result| {
+            let per_minute = result.amount * (1.0 + effect.productivity) * crafts_per_minute;
+            let ui_callback = move |ui: &mut Ui| {
+                unselectable_label(ui, format!("{} {}/min", this.display_name(&result.item), format_rate(per_minute)));
+            };
+            if this.is_fluid(&result.item) {
+                NodeOutput::new(ui_callback, move || FluidFlow { fluid: result.item.clone(), per_minute })
+            } else {
+                NodeOutput::new(ui_callback, move || ItemFlow { item: result.item.clone(), per_minute })
+            }
+        }).collect();
+        (inputs, Box::new(move |ui| {
+            ui.horizontal(|ui| {
+                unselectable_label(ui, if this.machines.borrow().is_some() { "Machines" } else { "Target" });
+                this.rate_ui(ui);
+            });
+            ui.horizontal(|ui| {
+                unselectable_label(ui, "Clock");
+                this.clock_speed_ui(ui);
+            });
+            match (this.machine(), this.machine_count()) {
+                (Some(machine), Some(count)) => {
+                    unselectable_label(ui, format!("{} × {} ({})", format_rate(count), machine.display_name, count.ceil()));
+                }
+                _ => {
+                    unselectable_label(ui, RichText::new("No machine can craft this").color(ui.visuals().error_fg_color));
+                }
+            }
+            if effect != Effect::default() {
+                unselectable_label(ui, effect.describe());
+            }
+            if let Some(power) = this.power() {
+                unselectable_label(ui, format!("⚡ {}", format_power(power)));
+            }
+        }), outputs)
+    }
+
+    fn inspector(&mut self, ui: &mut Ui) {
+        ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("Search recipes"));
+        let mut alternate_recipes = self.alternate_recipes.get();
+        if ui.checkbox(&mut alternate_recipes, "Use alternate recipes").on_hover_text("Applies to the whole project").changed() {
+            self.alternate_recipes.set(alternate_recipes);
+        }
+        let search = self.search.to_lowercase();
+        egui::ScrollArea::vertical().max_height(200.0).id_source("recipes").show(ui, |ui| {
+            for recipe in self.database.recipes.values() {
+                if (recipe.alternate && !alternate_recipes) || !recipe.display_name.to_lowercase().contains(&search) {
+                    continue;
+                }
+                let selected = self.recipe.borrow().as_ref() == Some(&recipe.name);
+                if ui.selectable_label(selected, &recipe.display_name).clicked() {
+                    self.set_recipe(&recipe.name);
+                }
+            }
+        });
+        let Some(recipe) = self.recipe() else {
+            return;
+        };
+        ui.separator();
+        let machine_name = self.machine().map_or("None", |machine| machine.display_name.as_str());
+        egui::ComboBox::from_label("Machine").selected_text(machine_name).show_ui(ui, |ui| {
+            for machine in self.database.machines.values().filter(|machine| machine.categories.contains(&recipe.category)) {
+                let selected = self.machine.borrow().as_ref() == Some(&machine.name);
+                if ui.selectable_label(selected, &machine.display_name).clicked() {
+                    self.machine.replace(Some(machine.name.clone()));
+                }
+            }
+        });
+        let mut fixed = self.machines.borrow().is_some();
+        if ui.checkbox(&mut fixed, "Fixed machine count").on_hover_text("Run a number of machines instead of working towards a target").changed() {
+            self.set_fixed_machines(fixed);
+        }
+        ui.horizontal(|ui| {
+            ui.label(if fixed { "Machines" } else { "Target" });
+            self.rate_ui(ui);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Clock speed");
+            self.clock_speed_ui(ui);
+        });
+        let module_slots = self.machine().map_or(0, |machine| machine.module_slots);
+        if module_slots > 0 {
+            ui.label("Modules");
+            ui.horizontal_wrapped(|ui| {
+                self.module_slots_ui(ui, "module", &self.modules, module_slots, |module| module.allowed_for(&recipe.name));
+            });
+        }
+        if !self.module_database.modules.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label("Beacons");
+                ui.add(egui::DragValue::new(&mut *self.beacons.borrow_mut()).range(0..=16));
+            });
+            if *self.beacons.borrow() > 0 {
+                ui.horizontal_wrapped(|ui| {
+                    let slots = self.module_database.beacon.module_slots;
+                    self.module_slots_ui(ui, "beacon module", &self.beacon_modules, slots, |module| module.allowed_for(&recipe.name) && module.allowed_in_beacons());
+                });
+            }
+            ui.label(self.effect().describe());
+        }
+        let (Some(crafts_per_machine), Some(count), Some(power_per_machine)) = (self.crafts_per_machine(), self.machine_count(), self.power_per_machine()) else {
+            return;
+        };
+        ui.label(format!("{} machines, {} to build", format_rate(count), count.ceil()));
+        ui.label(format!("Power: {} each, {} in total", format_power(power_per_machine), format_power(power_per_machine * count)));
+        ui.label("Each machine:");
+        let productivity = 1.0 + self.effect().productivity;
+        for (sign, amounts, scale) in [("-", &recipe.ingredients, 1.0), ("+", &recipe.results, productivity)] {
+            for amount in amounts {
+                ui.label(format!("{sign} {}/min {}", format_rate(amount.amount * scale * crafts_per_machine), self.display_name(&amount.item)));
+            }
+        }
+    }
+
+    fn power_draw(&self) -> f64 {
+        self.power().unwrap_or(0.0)
+    }
+
+    fn output_supply(&self, index: usize) -> Option<ItemFlow> {
+        let result = self.recipe()?.results.get(index)?;
+        Some(ItemFlow {
+            item: result.item.clone(),
+            per_minute: result.amount * (1.0 + self.effect().productivity) * self.crafts_per_minute(),
+        })
+    }
+
+    fn input_demand(&self, index: usize) -> Option<Demand> {
+        let ingredient = self.recipe()?.ingredients.get(index)?;
+        Some(Demand::Flow(ItemFlow {
+            item: ingredient.item.clone(),
+            per_minute: ingredient.amount * self.crafts_per_minute(),
+        }))
+    }
+
+    fn report(&self) -> NodeReport {
+        let Some(recipe) = self.recipe() else {
+            return NodeReport::default();
+        };
+        let crafts_per_minute = self.crafts_per_minute();
+        let productivity = 1.0 + self.effect().productivity;
+        NodeReport {
+            produced: recipe
+                .results
+                .iter()
+                .map(|result| (self.display_name(&result.item).to_owned(), result.amount * productivity * crafts_per_minute))
+                .collect(),
+            consumed: recipe
+                .ingredients
+                .iter()
+                .map(|ingredient| (self.display_name(&ingredient.item).to_owned(), ingredient.amount * crafts_per_minute))
+                .collect(),
+            buildings: self.machine().zip(self.machine_count()).map(|(machine, count)| (machine.display_name.clone(), count)).into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    fn errors(&self) -> Vec<String> {
+        let Some(recipe) = self.recipe() else {
+            return match &*self.recipe.borrow() {
+                Some(name) => vec![format!("The recipe {name} is not in the recipe database")],
+                None => vec!["No recipe is picked".to_owned()],
+            };
+        };
+        let mut errors: Vec<_> = (0..recipe.ingredients.len()).filter_map(|i| self.supply_problem(i)).collect();
+        if recipe.alternate && !self.alternate_recipes.get() {
+            errors.push(format!("{} is an alternate recipe, which the project doesn't use", recipe.display_name));
+        }
+        if self.machine().is_none() {
+            errors.push("No machine is picked".to_owned());
+        }
+        errors
+    }
+
+    fn save(&self) -> String {
+        serde_json::to_string(&SavedMachine {
+            recipe: self.recipe.borrow().clone(),
+            machine: self.machine.borrow().clone(),
+            target: *self.target.borrow(),
+            machines: *self.machines.borrow(),
+            clock_speed: *self.clock_speed.borrow(),
+            modules: self.modules.borrow().clone(),
+            beacons: *self.beacons.borrow(),
+            beacon_modules: self.beacon_modules.borrow().clone(),
+        })
+        .expect("serializing a machine can't fail")
+    }
+
+    fn load(&mut self, data: &str) {
+        if let Ok(saved) = serde_json::from_str::<SavedMachine>(data) {
+            self.recipe.replace(saved.recipe);
+            self.machine.replace(saved.machine);
+            self.target.replace(saved.target);
+            self.machines.replace(saved.machines);
+            self.clock_speed.replace(saved.clock_speed.clamp(0.01, 2.5));
+            self.modules.replace(saved.modules);
+            self.beacons.replace(saved.beacons);
+            self.beacon_modules.replace(saved.beacon_modules);
+        }
+    }
+}
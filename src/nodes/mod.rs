@@ -3,3 +3,8 @@ pub mod sink_node;
 pub mod source_node;
 pub mod one_to_n_node;
 pub mod graph_node;
+pub mod machine_node;
+pub mod generator_node;
+pub mod tank_node;
+pub mod pump_node;
+pub mod void_node;
@@ -1,8 +1,11 @@
 use std::cell::RefCell;
 use std::ops::AddAssign;
 
+use eframe::egui::Color32;
 use eframe::egui::Ui;
 
+use crate::theme::NodeStyle;
+use crate::theme::PortShape;
 use crate::unselectable_label;
 use crate::{node_input::NodeInput, node_output::NodeOutput, Node};
 
@@ -17,6 +20,14 @@ impl Node for OneToNNode {
         "OneToN"
     }
 
+    fn style(&self) -> NodeStyle {
+        NodeStyle {
+            header_color: Some(Color32::from_rgb(220, 140, 40)),
+            icon: Some("🔀".to_owned()),
+            port_shape: Some(PortShape::Diamond),
+        }
+    }
+
     fn body<'a>(
         &'a mut self,
     ) -> (
@@ -42,4 +53,19 @@ impl Node for OneToNNode {
             output_callbacks,
         )
     }
+
+    /// The outputs in use are saved, so links to them are kept when loading
+    fn save(&self) -> String {
+        self.output_count.borrow().to_string()
+    }
+
+    fn load(&mut self, data: &str) {
+        if let Ok(output_count) = data.parse() {
+            self.output_count.replace(output_count);
+        }
+    }
+
+    fn inspector(&mut self, ui: &mut Ui) {
+        ui.label(format!("Passing on {}", self.value_1.borrow()));
+    }
 }
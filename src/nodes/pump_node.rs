@@ -0,0 +1,145 @@
+use std::cell::RefCell;
+
+use eframe::egui;
+use eframe::egui::Color32;
+use eframe::egui::RichText;
+use eframe::egui::Ui;
+
+use crate::flow::format_power;
+use crate::flow::format_rate;
+use crate::flow::FluidFlow;
+use crate::report::NodeReport;
+use crate::theme::NodeStyle;
+use crate::unselectable_label;
+use crate::{node_input::NodeInput, node_output::NodeOutput, Node};
+
+/// The most fluid one pump moves per minute
+const PUMP_THROUGHPUT: f64 = 1200.0 * 60.0;
+/// The power one pump draws while pumping, in watts
+const PUMP_POWER: f64 = 30e3;
+
+/// Pumps in a pipeline, which split it into shorter pipes that can each carry more
+/// The pipes on either side of the pumps are separate links, each limited by its own length
+#[derive(Clone)]
+pub struct PumpNode {
+    count: RefCell<u32>,
+    /// The fluid linked in during the last frame
+    flow: RefCell<Option<FluidFlow>>,
+    /// The fluid linked in so far this frame
+    received: RefCell<Option<FluidFlow>>,
+}
+
+impl Default for PumpNode {
+    fn default() -> Self {
+        Self {
+            count: RefCell::new(1),
+            flow: RefCell::new(None),
+            received: RefCell::new(None),
+        }
+    }
+}
+
+impl PumpNode {
+    fn throughput(&self) -> f64 {
+        PUMP_THROUGHPUT * *self.count.borrow() as f64
+    }
+
+    /// The fluid pumped on, which is as much as flows in, up to what the pumps can move
+    fn pumped(&self) -> FluidFlow {
+        let mut flow = self.flow.borrow().clone().unwrap_or_default();
+        flow.per_minute = flow.per_minute.min(self.throughput());
+        flow
+    }
+
+    fn limited(&self) -> bool {
+        self.flow.borrow().as_ref().is_some_and(|flow| flow.per_minute > self.throughput())
+    }
+
+    fn count_ui(&self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            unselectable_label(ui, "Pumps");
+            ui.add(egui::DragValue::new(&mut *self.count.borrow_mut()).range(1..=1000));
+        });
+    }
+}
+
+impl Node for PumpNode {
+    fn title(&self) -> &str {
+        "Pump"
+    }
+
+    fn style(&self) -> NodeStyle {
+        NodeStyle {
+            header_color: Some(Color32::from_rgb(50, 130, 200)),
+            icon: Some("⛽".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn body<'a>(
+        &'a mut self,
+    ) -> (std::vec::Vec<NodeInput>, Box<(dyn FnOnce(&mut Ui) + 'a)>, std::vec::Vec<NodeOutput>) {
+        let received = self.received.take();
+        self.flow.replace(received);
+        let this = &*self;
+        (
+            vec![NodeInput::input(|flow: FluidFlow| {
+                this.received.replace(Some(flow));
+            })],
+            Box::new(|ui| {
+                this.count_ui(ui);
+                let text = format!("Up to {}/min", format_rate(this.throughput()));
+                if this.limited() {
+                    unselectable_label(ui, RichText::new(text).color(ui.visuals().error_fg_color));
+                } else {
+                    unselectable_label(ui, text);
+                }
+            }),
+            vec![NodeOutput::new(
+                |ui| {
+                    unselectable_label(ui, format!("{}/min", format_rate(this.pumped().per_minute)));
+                },
+                || this.pumped(),
+            )],
+        )
+    }
+
+    fn inspector(&mut self, ui: &mut Ui) {
+        self.count_ui(ui);
+        ui.label(format!("Moves up to {}/min", format_rate(self.throughput())));
+        ui.label(format!("Draws {} while pumping", format_power(self.power_draw())));
+    }
+
+    fn errors(&self) -> Vec<String> {
+        if self.limited() {
+            vec![format!("More fluid flows in than {} pumps can move", self.count.borrow())]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn power_draw(&self) -> f64 {
+        if self.pumped().per_minute > 0.0 {
+            PUMP_POWER * *self.count.borrow() as f64
+        } else {
+            0.0
+        }
+    }
+
+    fn report(&self) -> NodeReport {
+        NodeReport {
+            buildings: vec![("Pump".to_owned(), *self.count.borrow() as f64)],
+            ..Default::default()
+        }
+    }
+
+    fn save(&self) -> String {
+        self.count.borrow().to_string()
+    }
+
+    fn load(&mut self, data: &str) {
+        if let Ok(count) = data.parse() {
+            self.count.replace(count);
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use std::cell::RefCell;
 
+use eframe::egui::Color32;
 use eframe::egui::Ui;
 
+use crate::theme::NodeStyle;
 use crate::unselectable_label;
 use crate::{node_input::NodeInput, node_output::NodeOutput, Node};
 
@@ -15,6 +17,14 @@ impl Node for SinkNode {
         "Sink"
     }
 
+    fn style(&self) -> NodeStyle {
+        NodeStyle {
+            header_color: Some(Color32::from_rgb(60, 160, 80)),
+            icon: Some("📤".to_owned()),
+            ..Default::default()
+        }
+    }
+
     fn body<'a>(
         &'a mut self,
     ) -> (
@@ -33,4 +43,8 @@ impl Node for SinkNode {
             vec![],
         )
     }
+
+    fn inspector(&mut self, ui: &mut Ui) {
+        ui.label(format!("Received {}", self.value.borrow()));
+    }
 }
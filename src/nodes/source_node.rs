@@ -1,14 +1,29 @@
 use std::cell::RefCell;
 
 use eframe::egui;
+use eframe::egui::Color32;
 use eframe::egui::Ui;
 
+use crate::report::NodeReport;
+use crate::theme::NodeStyle;
 use crate::{node_input::NodeInput, node_output::NodeOutput, Node};
 
-
-#[derive(Default, Clone)]
+/// Brings a value into the graph
+/// The value counts as that much per minute of its resource in the graph's bill of materials
+#[derive(Clone)]
 pub struct SourceNode {
     value: RefCell<u8>,
+    /// The raw resource the value stands for
+    resource: RefCell<String>,
+}
+
+impl Default for SourceNode {
+    fn default() -> Self {
+        Self {
+            value: RefCell::new(0),
+            resource: RefCell::new("Source".to_owned()),
+        }
+    }
 }
 
 impl Node for SourceNode {
@@ -16,9 +31,45 @@ impl Node for SourceNode {
         "Source"
     }
 
+    fn style(&self) -> NodeStyle {
+        NodeStyle {
+            header_color: Some(Color32::from_rgb(60, 160, 80)),
+            icon: Some("📥".to_owned()),
+            ..Default::default()
+        }
+    }
+
     fn body<'a>(
         &'a mut self,
     ) -> (std::vec::Vec<NodeInput>, Box<(dyn FnOnce(&mut Ui) + 'a)>, std::vec::Vec<NodeOutput>) { 
         (vec![], Box::new(|_| {}), vec![NodeOutput::new(|ui| {ui.add(egui::Slider::new(&mut *self.value.borrow_mut(), 0..=u8::MAX));}, || self.value.borrow().clone())])
     }
+
+    fn inspector(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut *self.value.borrow_mut(), 0..=u8::MAX).text("Value"));
+        ui.horizontal(|ui| {
+            ui.label("Resource");
+            ui.text_edit_singleline(&mut *self.resource.borrow_mut());
+        });
+    }
+
+    fn report(&self) -> NodeReport {
+        NodeReport {
+            raw_resources: vec![(self.resource.borrow().clone(), *self.value.borrow() as f64)],
+            ..Default::default()
+        }
+    }
+
+    /// Saved as the value followed by the resource, older saves only have the value
+    fn save(&self) -> String {
+        format!("{} {}", self.value.borrow(), self.resource.borrow())
+    }
+
+    fn load(&mut self, data: &str) {
+        let (value, resource) = data.split_once(' ').unwrap_or((data, "Source"));
+        if let Ok(value) = value.parse() {
+            self.value.replace(value);
+            self.resource.replace(resource.to_owned());
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,126 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use eframe::egui;
+use eframe::egui::Color32;
+use eframe::egui::RichText;
+use eframe::egui::Ui;
+
+use crate::flow::format_rate;
+use crate::flow::FluidFlow;
+use crate::recipe_database::RecipeDatabase;
+use crate::report::NodeReport;
+use crate::theme::NodeStyle;
+use crate::unselectable_label;
+use crate::{node_input::NodeInput, node_output::NodeOutput, Node};
+
+/// How much fluid one tank holds
+const TANK_CAPACITY: f64 = 25_000.0;
+
+/// Storage tanks that the fluid passes through, pooling it as a buffer
+#[derive(Clone)]
+pub struct TankNode {
+    database: Rc<RecipeDatabase>,
+    count: RefCell<u32>,
+    /// The fluid linked in during the last frame
+    flow: RefCell<Option<FluidFlow>>,
+    /// The fluid linked in so far this frame
+    received: RefCell<Option<FluidFlow>>,
+}
+
+impl TankNode {
+    /// Fluids are shown by their names in `database`
+    pub fn new(database: Rc<RecipeDatabase>) -> Self {
+        Self {
+            database,
+            count: RefCell::new(1),
+            flow: RefCell::new(None),
+            received: RefCell::new(None),
+        }
+    }
+
+    fn capacity(&self) -> f64 {
+        TANK_CAPACITY * *self.count.borrow() as f64
+    }
+
+    fn flow_text(&self) -> String {
+        match &*self.flow.borrow() {
+            Some(flow) if !flow.fluid.is_empty() => format!("{} {}/min", self.database.display_name(&flow.fluid), format_rate(flow.per_minute)),
+            _ => "Empty".to_owned(),
+        }
+    }
+}
+
+impl Node for TankNode {
+    fn title(&self) -> &str {
+        "Tank"
+    }
+
+    fn style(&self) -> NodeStyle {
+        NodeStyle {
+            header_color: Some(Color32::from_rgb(50, 130, 200)),
+            icon: Some("🛢".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn body<'a>(
+        &'a mut self,
+    ) -> (std::vec::Vec<NodeInput>, Box<(dyn FnOnce(&mut Ui) + 'a)>, std::vec::Vec<NodeOutput>) {
+        let received = self.received.take();
+        self.flow.replace(received);
+        let this = &*self;
+        (
+            vec![NodeInput::new(
+                |ui| {
+                    unselectable_label(ui, this.flow_text());
+                },
+                |flow: FluidFlow| {
+                    this.received.replace(Some(flow));
+                },
+            )],
+            Box::new(|ui| {
+                ui.horizontal(|ui| {
+                    unselectable_label(ui, "Tanks");
+                    ui.add(egui::DragValue::new(&mut *this.count.borrow_mut()).range(1..=1000));
+                });
+                unselectable_label(ui, format!("Holds {}", format_rate(this.capacity())));
+            }),
+            vec![NodeOutput::output(|| this.flow.borrow().clone().unwrap_or_default())],
+        )
+    }
+
+    fn inspector(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Tanks");
+            ui.add(egui::DragValue::new(&mut *self.count.borrow_mut()).range(1..=1000));
+        });
+        ui.label(format!("Holds {}", format_rate(self.capacity())));
+        ui.label(self.flow_text());
+        match &*self.flow.borrow() {
+            Some(flow) if flow.per_minute > 0.0 => {
+                ui.label(format!("Fills in {} min without anything drawing from it", format_rate(self.capacity() / flow.per_minute)));
+            }
+            _ => {
+                ui.label(RichText::new("Nothing flows in").weak());
+            }
+        }
+    }
+
+    fn report(&self) -> NodeReport {
+        NodeReport {
+            buildings: vec![("Storage tank".to_owned(), *self.count.borrow() as f64)],
+            ..Default::default()
+        }
+    }
+
+    fn save(&self) -> String {
+        self.count.borrow().to_string()
+    }
+
+    fn load(&mut self, data: &str) {
+        if let Ok(count) = data.parse() {
+            self.count.replace(count);
+        }
+    }
+}
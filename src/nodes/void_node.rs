@@ -0,0 +1,105 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use eframe::egui::Color32;
+use eframe::egui::RichText;
+use eframe::egui::Ui;
+
+use crate::flow::format_rate;
+use crate::flow::Demand;
+use crate::flow::FluidFlow;
+use crate::flow::ItemFlow;
+use crate::recipe_database::RecipeDatabase;
+use crate::theme::NodeStyle;
+use crate::unselectable_label;
+use crate::{node_input::NodeInput, node_output::NodeOutput, Node};
+
+/// Gets rid of excess, by voiding it or feeding it to a sink
+/// Takes whatever is left over from the outputs linked to it, so the graph counts their surplus as dealt with
+/// Has one input for items and one for fluids
+#[derive(Clone)]
+pub struct VoidNode {
+    database: Rc<RecipeDatabase>,
+    /// The item and fluid linked in during the last frame
+    flows: RefCell<[Option<ItemFlow>; 2]>,
+    /// The item and fluid linked in so far this frame
+    received: RefCell<[Option<ItemFlow>; 2]>,
+}
+
+impl VoidNode {
+    /// Items and fluids are shown by their names in `database`
+    pub fn new(database: Rc<RecipeDatabase>) -> Self {
+        Self {
+            database,
+            flows: RefCell::default(),
+            received: RefCell::default(),
+        }
+    }
+
+    fn flow_text(&self, index: usize) -> String {
+        match &self.flows.borrow()[index] {
+            Some(flow) if !flow.item.is_empty() => format!("{} {}/min", self.database.display_name(&flow.item), format_rate(flow.per_minute)),
+            _ if index == 0 => "Items".to_owned(),
+            _ => "Fluids".to_owned(),
+        }
+    }
+}
+
+impl Node for VoidNode {
+    fn title(&self) -> &str {
+        "Void"
+    }
+
+    fn style(&self) -> NodeStyle {
+        NodeStyle {
+            header_color: Some(Color32::from_rgb(90, 90, 90)),
+            icon: Some("🗑".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn body<'a>(
+        &'a mut self,
+    ) -> (std::vec::Vec<NodeInput>, Box<(dyn FnOnce(&mut Ui) + 'a)>, std::vec::Vec<NodeOutput>) {
+        let received = self.received.take();
+        self.flows.replace(received);
+        let this = &*self;
+        (
+            vec![
+                NodeInput::new(
+                    |ui| {
+                        unselectable_label(ui, this.flow_text(0));
+                    },
+                    |flow: ItemFlow| {
+                        this.received.borrow_mut()[0] = Some(flow);
+                    },
+                ),
+                NodeInput::new(
+                    |ui| {
+                        unselectable_label(ui, this.flow_text(1));
+                    },
+                    |flow: FluidFlow| {
+                        this.received.borrow_mut()[1] = Some(ItemFlow { item: flow.fluid, per_minute: flow.per_minute });
+                    },
+                ),
+            ],
+            Box::new(|_| {}),
+            vec![],
+        )
+    }
+
+    fn inspector(&mut self, ui: &mut Ui) {
+        let flows = self.flows.borrow();
+        if flows.iter().all(Option::is_none) {
+            ui.label(RichText::new("Nothing is linked in").weak());
+        }
+        for flow in flows.iter().flatten() {
+            ui.label(format!("Linked to {}/min of {}", format_rate(flow.per_minute), self.database.display_name(&flow.item)));
+        }
+        ui.weak("Only what isn't used by other inputs linked to the same outputs is voided");
+    }
+
+    fn input_demand(&self, _index: usize) -> Option<Demand> {
+        Some(Demand::Excess)
+    }
+}
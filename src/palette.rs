@@ -0,0 +1,69 @@
+/// Scores how well `query` matches `text`, or `None` if the characters of `query` don't all appear in `text` in order
+/// Case and whitespace in the query are ignored
+/// Characters matched at the start of a word or right after the previous match score higher,
+/// and skipping over characters of `text` between matches scores lower
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut next_index = 0;
+    for query_char in query.chars().flat_map(char::to_lowercase).filter(|c| !c.is_whitespace()) {
+        let found = next_index + text[next_index..].iter().position(|c| *c == query_char)?;
+        score += 1;
+        if found == next_index && found > 0 {
+            score += 4;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (found - next_index).min(3) as i32;
+        next_index = found + 1;
+    }
+    Some(score)
+}
+
+/// The best score of `query` against a node's title, category and keywords, see `fuzzy_score`
+/// Title matches are preferred, so a node named after the query comes before one merely tagged with it
+pub fn node_score(query: &str, title: &str, category: &str, keywords: &[String]) -> Option<i32> {
+    let title_score = fuzzy_score(query, title).map(|score| score + 2);
+    std::iter::once(title_score)
+        .chain(std::iter::once(fuzzy_score(query, category)))
+        .chain(keywords.iter().map(|keyword| fuzzy_score(query, keyword)))
+        .flatten()
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_matches_characters_in_order() {
+        assert_eq!(fuzzy_score("abc", "abc"), Some(14));
+        assert_eq!(fuzzy_score("ac", "abc"), Some(4));
+        assert_eq!(fuzzy_score("ca", "abc"), None);
+        assert_eq!(fuzzy_score("abd", "abc"), None);
+        assert_eq!(fuzzy_score("", "abc"), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_score_ignores_case_and_spaces_in_the_query() {
+        assert_eq!(fuzzy_score("ABC", "abc"), fuzzy_score("abc", "ABC"));
+        assert_eq!(fuzzy_score("a c", "abc"), fuzzy_score("ac", "abc"));
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_runs_and_word_starts() {
+        assert!(fuzzy_score("ste", "steam engine") > fuzzy_score("ste", "sorted"));
+        assert!(fuzzy_score("sb", "steam boiler") > fuzzy_score("sb", "subway"));
+    }
+
+    #[test]
+    fn node_score_prefers_the_title() {
+        let keywords = ["sum".to_owned(), "plus".to_owned()];
+        assert_eq!(node_score("add", "Adder", "Math", &keywords), Some(16));
+        assert_eq!(node_score("sum", "Adder", "Math", &keywords), fuzzy_score("sum", "sum"));
+        assert_eq!(node_score("math", "Adder", "Math", &keywords), fuzzy_score("math", "math"));
+        assert_eq!(node_score("pump", "Adder", "Math", &keywords), None);
+        assert!(node_score("plus", "Plus", "Math", &[]) > node_score("plus", "Adder", "Math", &keywords));
+    }
+}
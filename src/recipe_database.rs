@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Whether something is carried around as items or piped as a fluid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItemKind {
+    Item,
+    Fluid,
+}
+
+/// Anything recipes use or make
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    pub name: String,
+    pub display_name: String,
+    pub kind: ItemKind,
+    /// How many fit in one inventory slot, `None` for fluids and games without stacks
+    pub stack_size: Option<u32>,
+}
+
+/// An amount of an item, used for both the ingredients and the results of a recipe
+/// Results that only come out some of the time use their average amount
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ItemAmount {
+    pub item: String,
+    pub amount: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Recipe {
+    pub name: String,
+    pub display_name: String,
+    /// Machines can craft the recipe if they have its category
+    pub category: String,
+    /// How long one craft takes at a crafting speed of 1, in seconds
+    pub time: f64,
+    pub ingredients: Vec<ItemAmount>,
+    pub results: Vec<ItemAmount>,
+    /// Alternate recipes have to be unlocked separately, so projects choose which of them to use
+    #[serde(default)]
+    pub alternate: bool,
+}
+
+/// A building that crafts recipes
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Machine {
+    pub name: String,
+    pub display_name: String,
+    pub crafting_speed: f64,
+    /// The recipe categories the machine can craft
+    pub categories: Vec<String>,
+    /// Power drawn while crafting, in watts
+    pub power: f64,
+    /// How power draw scales with clock speed, which draws `power * clock_speed.powf(power_exponent)`
+    #[serde(default = "default_power_exponent")]
+    pub power_exponent: f64,
+    #[serde(default)]
+    pub module_slots: u32,
+}
+
+fn default_power_exponent() -> f64 {
+    1.0
+}
+
+/// Everything known about the items, recipes and machines of a game, keyed by their internal names
+/// Display names are the in game names where the data has them, and the internal names otherwise
+/// The database is imported from a game's data with one of the `importers`, and saved as JSON
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecipeDatabase {
+    pub items: BTreeMap<String, Item>,
+    pub recipes: BTreeMap<String, Recipe>,
+    /// Recipes for pieces that are placed rather than crafted in a machine, like the build gun recipes of Satisfactory
+    /// Machines never offer them, they are only used to work out construction costs
+    #[serde(default)]
+    pub construction_recipes: BTreeMap<String, Recipe>,
+    pub machines: BTreeMap<String, Machine>,
+}
+
+impl RecipeDatabase {
+    /// Reads a database written by `save`
+    pub fn load(text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+
+    pub fn save(&self) -> String {
+        serde_json::to_string_pretty(self).expect("serializing a recipe database can't fail")
+    }
+
+    /// The name the game shows for an item or fluid, or its internal name when it isn't in the database
+    pub fn display_name<'a>(&'a self, item: &'a str) -> &'a str {
+        self.items.get(item).map_or(item, |item| item.display_name.as_str())
+    }
+}
+
+/// Why game data couldn't be imported
+#[derive(Debug)]
+pub enum ImportError {
+    Json(serde_json::Error),
+    /// The file is valid JSON, but not laid out like the game's data
+    Format(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Json(error) => write!(f, "invalid JSON: {error}"),
+            ImportError::Format(message) => write!(f, "unexpected data: {message}"),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(error: serde_json::Error) -> Self {
+        ImportError::Json(error)
+    }
+}
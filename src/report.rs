@@ -0,0 +1,445 @@
+use crate::flow::format_power;
+use crate::flow::format_rate;
+use crate::recipe_database::Recipe;
+use crate::recipe_database::RecipeDatabase;
+
+/// What one node adds to the report of its graph, returned by `Node::report`
+/// Amounts are per minute, keyed by display name
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeReport {
+    /// Raw resources the node brings into the factory, like ore from a source
+    pub raw_resources: Vec<(String, f64)>,
+    pub produced: Vec<(String, f64)>,
+    pub consumed: Vec<(String, f64)>,
+    /// The buildings the node needs, with how many, which isn't always a whole number
+    pub buildings: Vec<(String, f64)>,
+    /// The links carrying items, each of which is built from belts
+    pub belt_links: u32,
+    /// The pipes making up the links carrying fluids
+    pub pipes: u32,
+}
+
+/// A building in a report
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuildingCount {
+    pub name: String,
+    /// How many are needed to build, with each node's count rounded up
+    pub count: u64,
+    /// How many would be needed if they could all run at full speed
+    pub exact: f64,
+}
+
+/// The bill of materials of a whole graph
+/// Items consumed that no node produces, or more of than the nodes produce, count as raw resources, along with the ones sources bring in
+/// Items both produced and consumed are intermediates, and whatever is produced beyond what is consumed is a product
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub raw_resources: Vec<(String, f64)>,
+    pub intermediates: Vec<(String, f64)>,
+    pub products: Vec<(String, f64)>,
+    pub buildings: Vec<BuildingCount>,
+    pub belt_links: u32,
+    pub pipes: u32,
+    /// In watts
+    pub power_draw: f64,
+    /// In watts
+    pub power_supply: f64,
+}
+
+/// Adds `amount` to the entry for `name`, keeping entries in the order they were first added
+fn add(totals: &mut Vec<(String, f64)>, name: &str, amount: f64) {
+    match totals.iter_mut().find(|(existing, _)| existing == name) {
+        Some((_, total)) => *total += amount,
+        None => totals.push((name.to_owned(), amount)),
+    }
+}
+
+/// Quotes a CSV field if it needs it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+impl Report {
+    /// Combines the reports of the nodes of a graph, along with the power they draw and supply
+    pub fn new(node_reports: impl IntoIterator<Item = NodeReport>, power_draw: f64, power_supply: f64) -> Self {
+        let mut raw_resources = Vec::new();
+        let mut produced = Vec::new();
+        let mut consumed = Vec::new();
+        let mut buildings: Vec<BuildingCount> = Vec::new();
+        let (mut belt_links, mut pipes) = (0, 0);
+        for node_report in node_reports {
+            belt_links += node_report.belt_links;
+            pipes += node_report.pipes;
+            for (name, amount) in node_report.raw_resources {
+                add(&mut raw_resources, &name, amount);
+            }
+            for (name, amount) in node_report.produced {
+                add(&mut produced, &name, amount);
+            }
+            for (name, amount) in node_report.consumed {
+                add(&mut consumed, &name, amount);
+            }
+            for (name, exact) in node_report.buildings {
+                match buildings.iter_mut().find(|building| building.name == name) {
+                    Some(building) => {
+                        building.count += exact.ceil() as u64;
+                        building.exact += exact;
+                    }
+                    None => buildings.push(BuildingCount { count: exact.ceil() as u64, name, exact }),
+                }
+            }
+        }
+        let mut intermediates = Vec::new();
+        for (name, amount) in consumed.iter() {
+            match produced.iter().find(|(produced_name, _)| produced_name == name) {
+                Some((_, produced_amount)) => {
+                    add(&mut intermediates, name, amount.min(*produced_amount));
+                    // Whatever the graph doesn't make enough of has to be brought in
+                    if amount - produced_amount > 1e-6 {
+                        add(&mut raw_resources, name, amount - produced_amount);
+                    }
+                }
+                None => add(&mut raw_resources, name, *amount),
+            }
+        }
+        let products = produced
+            .into_iter()
+            .filter_map(|(name, amount)| {
+                let consumed_amount = consumed.iter().find(|(consumed_name, _)| *consumed_name == name).map_or(0.0, |(_, amount)| *amount);
+                Some((name, amount - consumed_amount)).filter(|(_, left)| *left > 1e-6)
+            })
+            .collect();
+        Self {
+            raw_resources,
+            intermediates,
+            products,
+            buildings,
+            belt_links,
+            pipes,
+            power_draw,
+            power_supply,
+        }
+    }
+
+    /// The report as rows of (section, name, amount, unit)
+    fn rows(&self) -> Vec<(&'static str, &str, String, &'static str)> {
+        let mut rows = Vec::new();
+        for (section, amounts) in [("Raw resource", &self.raw_resources), ("Intermediate", &self.intermediates), ("Product", &self.products)] {
+            for (name, amount) in amounts {
+                rows.push((section, name.as_str(), format_rate(*amount), "per minute"));
+            }
+        }
+        for building in self.buildings.iter() {
+            rows.push(("Building", building.name.as_str(), building.count.to_string(), "buildings"));
+        }
+        rows.push(("Power", "Drawn", format_rate(self.power_draw), "W"));
+        rows.push(("Power", "Generated", format_rate(self.power_supply), "W"));
+        rows
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = "Section,Name,Amount,Unit\n".to_owned();
+        for (section, name, amount, unit) in self.rows() {
+            csv += &format!("{section},{},{amount},{unit}\n", csv_field(name));
+        }
+        csv
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = "# Bill of materials\n".to_owned();
+        let table = |title: &str, amounts: &[(String, f64)]| {
+            let mut table = format!("\n## {title}\n\n| Name | Per minute |\n| --- | ---: |\n");
+            for (name, amount) in amounts {
+                table += &format!("| {} | {} |\n", name.replace('|', "\\|"), format_rate(*amount));
+            }
+            table
+        };
+        markdown += &table("Raw resources", &self.raw_resources);
+        markdown += &table("Intermediate products", &self.intermediates);
+        markdown += &table("Products", &self.products);
+        markdown += "\n## Buildings\n\n| Name | Count | Exact |\n| --- | ---: | ---: |\n";
+        for building in self.buildings.iter() {
+            markdown += &format!("| {} | {} | {} |\n", building.name.replace('|', "\\|"), building.count, format_rate(building.exact));
+        }
+        markdown += &format!(
+            "\n## Power\n\n| Drawn | Generated |\n| ---: | ---: |\n| {} | {} |\n",
+            format_power(self.power_draw),
+            format_power(self.power_supply),
+        );
+        markdown
+    }
+}
+
+/// How `ConstructionCost` turns the buildings and links of a graph into materials
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstructionSettings {
+    /// The belts laid for each link carrying items
+    pub belts_per_link: u32,
+    /// The internal names of belts and pipes in the recipe database
+    pub belt_item: String,
+    pub pipe_item: String,
+    /// Expand the materials through their recipes down to raw resources,
+    /// instead of stopping at the ingredients of the buildings
+    pub recursive: bool,
+}
+
+impl Default for ConstructionSettings {
+    fn default() -> Self {
+        Self {
+            belts_per_link: 10,
+            belt_item: "transport-belt".to_owned(),
+            pipe_item: "pipe".to_owned(),
+            recursive: false,
+        }
+    }
+}
+
+/// What it takes to build a graph, worked out from the recipes making its buildings, belts and pipes
+/// All names are display names
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConstructionCost {
+    /// The buildings, belts and pipes to place
+    pub pieces: Vec<(String, f64)>,
+    /// The materials the pieces are crafted from, pieces without a recipe count as their own material
+    pub materials: Vec<(String, f64)>,
+    /// Buildings that aren't items in the recipe database, so are left out of the materials
+    pub unknown: Vec<String>,
+}
+
+/// The recipe used to craft `item` for construction, along with how many of the item one craft makes
+/// Construction recipes come first, then recipes named after the item, then other recipes that aren't alternates, then alternates
+/// Recipes using up what they make, like ones filling barrels, are never used
+fn construction_recipe<'a>(database: &'a RecipeDatabase, item: &str) -> Option<(&'a Recipe, f64)> {
+    let makes = |recipe: &'a Recipe| {
+        let amount = recipe.results.iter().find(|result| result.item == item)?.amount;
+        let uses_up = recipe.ingredients.iter().any(|ingredient| ingredient.item == item);
+        Some((recipe, amount)).filter(|_| amount > 0.0 && !uses_up)
+    };
+    let recipes = || database.recipes.values();
+    database
+        .construction_recipes
+        .values()
+        .chain(database.recipes.get(item))
+        .chain(recipes().filter(|recipe| !recipe.alternate))
+        .chain(recipes().filter(|recipe| recipe.alternate))
+        .find_map(makes)
+}
+
+/// Adds the materials for `amount` of `item` to `materials`, going down `levels` recipes
+/// `path` holds the items being expanded, so recipe loops stop instead of going on forever
+fn expand(database: &RecipeDatabase, item: &str, amount: f64, levels: u32, path: &mut Vec<String>, materials: &mut Vec<(String, f64)>) {
+    let recipe = construction_recipe(database, item).filter(|_| levels > 0 && !path.iter().any(|expanded| expanded == item));
+    let Some((recipe, made)) = recipe else {
+        add(materials, item, amount);
+        return;
+    };
+    path.push(item.to_owned());
+    for ingredient in recipe.ingredients.iter() {
+        expand(database, &ingredient.item, ingredient.amount * amount / made, levels - 1, path, materials);
+    }
+    path.pop();
+}
+
+impl ConstructionCost {
+    /// Looks up the buildings of `report` in the recipe database by their display names, along with the belts and pipes
+    /// Each node's buildings are rounded up, as only whole buildings can be placed
+    pub fn new(report: &Report, database: &RecipeDatabase, settings: &ConstructionSettings) -> Self {
+        let mut pieces = Vec::new();
+        let mut unknown = Vec::new();
+        for building in report.buildings.iter() {
+            match database.items.values().find(|item| item.display_name == building.name || item.name == building.name) {
+                Some(item) => add(&mut pieces, &item.name, building.count as f64),
+                None => unknown.push(building.name.clone()),
+            }
+        }
+        for (item, count) in [(&settings.belt_item, report.belt_links * settings.belts_per_link), (&settings.pipe_item, report.pipes)] {
+            if count > 0 {
+                add(&mut pieces, item, count as f64);
+            }
+        }
+        let levels = if settings.recursive { u32::MAX } else { 1 };
+        let mut materials = Vec::new();
+        for (item, count) in pieces.iter() {
+            expand(database, item, *count, levels, &mut Vec::new(), &mut materials);
+        }
+        let display_names = |amounts: Vec<(String, f64)>| {
+            amounts
+                .into_iter()
+                .map(|(name, amount)| (database.items.get(&name).map_or(name, |item| item.display_name.clone()), amount))
+                .collect()
+        };
+        Self {
+            pieces: display_names(pieces),
+            materials: display_names(materials),
+            unknown,
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = "Section,Name,Amount\n".to_owned();
+        for (section, amounts) in [("Piece", &self.pieces), ("Material", &self.materials)] {
+            for (name, amount) in amounts {
+                csv += &format!("{section},{},{}\n", csv_field(name), format_rate(*amount));
+            }
+        }
+        for name in self.unknown.iter() {
+            csv += &format!("Unknown,{},\n", csv_field(name));
+        }
+        csv
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = "# Construction cost\n".to_owned();
+        for (title, amounts) in [("Pieces", &self.pieces), ("Materials", &self.materials)] {
+            markdown += &format!("\n## {title}\n\n| Name | Amount |\n| --- | ---: |\n");
+            for (name, amount) in amounts {
+                markdown += &format!("| {} | {} |\n", name.replace('|', "\\|"), format_rate(*amount));
+            }
+        }
+        if !self.unknown.is_empty() {
+            markdown += "\nNot in the recipe database, so left out: ";
+            markdown += &self.unknown.join(", ");
+            markdown += "\n";
+        }
+        markdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipe_database::Item;
+    use crate::recipe_database::ItemAmount;
+    use crate::recipe_database::ItemKind;
+
+    fn amounts(amounts: &[(&str, f64)]) -> Vec<(String, f64)> {
+        amounts.iter().map(|(name, amount)| ((*name).to_owned(), *amount)).collect()
+    }
+
+    fn gears_from_plates() -> Report {
+        let smelting = NodeReport {
+            produced: amounts(&[("Iron plate", 60.0)]),
+            consumed: amounts(&[("Iron ore", 60.0)]),
+            buildings: amounts(&[("Stone furnace", 1.6)]),
+            ..Default::default()
+        };
+        let gears = NodeReport {
+            produced: amounts(&[("Iron gear wheel", 20.0)]),
+            consumed: amounts(&[("Iron plate", 40.0)]),
+            buildings: amounts(&[("Assembling machine 1", 0.6)]),
+            ..Default::default()
+        };
+        let source = NodeReport {
+            raw_resources: amounts(&[("Coal", 10.0)]),
+            ..Default::default()
+        };
+        Report::new([smelting, gears, source], 150_000.0, 0.0)
+    }
+
+    #[test]
+    fn sorts_items_into_raw_resources_intermediates_and_products() {
+        let report = gears_from_plates();
+        assert_eq!(report.raw_resources, amounts(&[("Coal", 10.0), ("Iron ore", 60.0)]));
+        assert_eq!(report.intermediates, amounts(&[("Iron plate", 40.0)]));
+        assert_eq!(report.products, amounts(&[("Iron plate", 20.0), ("Iron gear wheel", 20.0)]));
+        assert_eq!(report.buildings[0], BuildingCount { name: "Stone furnace".to_owned(), count: 2, exact: 1.6 });
+    }
+
+    #[test]
+    fn intermediates_the_graph_makes_too_little_of_are_also_raw_resources() {
+        let smelting = NodeReport {
+            produced: amounts(&[("Iron plate", 30.0)]),
+            consumed: amounts(&[("Iron ore", 30.0)]),
+            ..Default::default()
+        };
+        let gears = NodeReport {
+            produced: amounts(&[("Iron gear wheel", 20.0)]),
+            consumed: amounts(&[("Iron plate", 40.0)]),
+            ..Default::default()
+        };
+        let report = Report::new([smelting, gears], 0.0, 0.0);
+        assert_eq!(report.raw_resources, amounts(&[("Iron ore", 30.0), ("Iron plate", 10.0)]));
+        assert_eq!(report.intermediates, amounts(&[("Iron plate", 30.0)]));
+        assert_eq!(report.products, amounts(&[("Iron gear wheel", 20.0)]));
+    }
+
+    #[test]
+    fn exports_csv_and_markdown() {
+        let report = gears_from_plates();
+        let csv = report.to_csv();
+        assert!(csv.starts_with("Section,Name,Amount,Unit\nRaw resource,Coal,10,per minute\n"));
+        assert!(csv.contains("Building,Assembling machine 1,1,buildings\n"));
+        assert!(csv.ends_with("Power,Drawn,150000,W\nPower,Generated,0,W\n"));
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("| Iron gear wheel | 20 |\n"));
+        assert!(markdown.contains("| Stone furnace | 2 | 1.6 |\n"));
+        assert!(markdown.contains("| 150 kW | 0 W |\n"));
+    }
+
+    fn construction_database() -> RecipeDatabase {
+        let mut database = RecipeDatabase::default();
+        for (name, display_name) in [
+            ("assembling-machine-1", "Assembling machine 1"),
+            ("electronic-circuit", "Electronic circuit"),
+            ("iron-gear-wheel", "Iron gear wheel"),
+            ("iron-ore", "Iron ore"),
+            ("iron-plate", "Iron plate"),
+            ("transport-belt", "Transport belt"),
+        ] {
+            let item = Item { name: name.to_owned(), display_name: display_name.to_owned(), kind: ItemKind::Item, stack_size: Some(50) };
+            database.items.insert(name.to_owned(), item);
+        }
+        // Each recipe with its ingredients and how many it makes
+        type Ingredients = &'static [(&'static str, f64)];
+        let recipes: [(&str, Ingredients, f64); 4] = [
+            ("assembling-machine-1", &[("electronic-circuit", 3.0), ("iron-gear-wheel", 5.0), ("iron-plate", 9.0)], 1.0),
+            ("iron-gear-wheel", &[("iron-plate", 2.0)], 1.0),
+            ("iron-plate", &[("iron-ore", 1.0)], 1.0),
+            ("transport-belt", &[("iron-gear-wheel", 1.0), ("iron-plate", 1.0)], 2.0),
+        ];
+        for (name, ingredients, made) in recipes {
+            let recipe = Recipe {
+                name: name.to_owned(),
+                display_name: name.to_owned(),
+                category: "crafting".to_owned(),
+                time: 0.5,
+                ingredients: ingredients.iter().map(|(item, amount)| ItemAmount { item: (*item).to_owned(), amount: *amount }).collect(),
+                results: vec![ItemAmount { item: name.to_owned(), amount: made }],
+                alternate: false,
+            };
+            database.recipes.insert(name.to_owned(), recipe);
+        }
+        database
+    }
+
+    fn two_assemblers() -> Report {
+        let assemblers = NodeReport {
+            buildings: amounts(&[("Assembling machine 1", 1.5), ("Mystery building", 1.0)]),
+            belt_links: 1,
+            ..Default::default()
+        };
+        Report::new([assemblers], 0.0, 0.0)
+    }
+
+    #[test]
+    fn expands_buildings_and_belts_into_their_ingredients() {
+        let cost = ConstructionCost::new(&two_assemblers(), &construction_database(), &ConstructionSettings::default());
+        assert_eq!(cost.pieces, amounts(&[("Assembling machine 1", 2.0), ("Transport belt", 10.0)]));
+        assert_eq!(cost.materials, amounts(&[("Electronic circuit", 6.0), ("Iron gear wheel", 15.0), ("Iron plate", 23.0)]));
+        assert_eq!(cost.unknown, vec!["Mystery building".to_owned()]);
+    }
+
+    #[test]
+    fn expands_recursively_down_to_raw_resources() {
+        let settings = ConstructionSettings { recursive: true, ..Default::default() };
+        let cost = ConstructionCost::new(&two_assemblers(), &construction_database(), &settings);
+        // Circuits have no recipe here, so they count as raw
+        assert_eq!(cost.materials, amounts(&[("Electronic circuit", 6.0), ("Iron ore", 53.0)]));
+        assert!(cost.to_csv().contains("Material,Iron ore,53\n"));
+        assert!(cost.to_markdown().contains("left out: Mystery building\n"));
+    }
+}
@@ -0,0 +1,75 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::flow::DEFAULT_PIPE_LENGTH;
+
+/// A single node in serialized form
+/// `type_name` and `data` come from `Node::type_name` and `Node::save`
+/// Positions are stored as plain arrays, so the format doesn't change along with how egui serializes its types
+#[derive(Serialize, Deserialize)]
+pub struct SerializedNode {
+    pub type_name: String,
+    pub position: [f32; 2],
+    pub data: String,
+    #[serde(default)]
+    pub collapsed: bool,
+    #[serde(default)]
+    pub body_size: Option<[f32; 2]>,
+}
+
+/// A link between two serialized nodes
+/// The ends are (node, port) pairs, where the node is an index into `SerializedNodes::nodes`
+#[derive(Serialize, Deserialize)]
+pub struct SerializedLink {
+    pub input: (usize, usize),
+    pub output: (usize, usize),
+    #[serde(default)]
+    pub reroutes: Vec<[f32; 2]>,
+    #[serde(default = "default_pipe_length")]
+    pub pipe_length: u32,
+}
+
+fn default_pipe_length() -> u32 {
+    DEFAULT_PIPE_LENGTH
+}
+
+/// A group of nodes and the links between them, as put on the clipboard
+#[derive(Serialize, Deserialize)]
+pub struct SerializedNodes {
+    pub nodes: Vec<SerializedNode>,
+    pub links: Vec<SerializedLink>,
+}
+
+/// A named view of the graph
+#[derive(Serialize, Deserialize)]
+pub struct SerializedBookmark {
+    pub name: String,
+    pub translation: [f32; 2],
+    pub scaling: f32,
+}
+
+/// A comment frame or note
+/// `color` is premultiplied RGBA
+#[derive(Serialize, Deserialize)]
+pub struct SerializedComment {
+    pub text: String,
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub color: [u8; 4],
+    pub frame: bool,
+}
+
+/// A whole graph, as saved to a file
+/// The nodes are flattened in, so a group of nodes can also be loaded as a graph
+#[derive(Serialize, Deserialize)]
+pub struct SerializedGraph {
+    #[serde(flatten)]
+    pub nodes: SerializedNodes,
+    #[serde(default)]
+    pub bookmarks: Vec<SerializedBookmark>,
+    #[serde(default)]
+    pub comments: Vec<SerializedComment>,
+    /// Whether the project uses alternate recipes
+    #[serde(default)]
+    pub alternate_recipes: bool,
+}
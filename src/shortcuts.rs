@@ -0,0 +1,195 @@
+use eframe::egui::Context;
+use eframe::egui::Key;
+use eframe::egui::KeyboardShortcut;
+use eframe::egui::Modifiers;
+
+/// Something the graph can do from the keyboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Delete,
+    Duplicate,
+    SelectAll,
+    FrameAll,
+    QuickAdd,
+    NudgeLeft,
+    NudgeRight,
+    NudgeUp,
+    NudgeDown,
+    FocusNext,
+    FocusPrevious,
+    /// Starts a link from the focused port, or finishes one started from another port
+    StartLink,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::Delete,
+        Action::Duplicate,
+        Action::SelectAll,
+        Action::FrameAll,
+        Action::QuickAdd,
+        Action::NudgeLeft,
+        Action::NudgeRight,
+        Action::NudgeUp,
+        Action::NudgeDown,
+        Action::FocusNext,
+        Action::FocusPrevious,
+        Action::StartLink,
+    ];
+
+    /// The name of the action in shortcut config files
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Delete => "delete",
+            Action::Duplicate => "duplicate",
+            Action::SelectAll => "select_all",
+            Action::FrameAll => "frame_all",
+            Action::QuickAdd => "quick_add",
+            Action::NudgeLeft => "nudge_left",
+            Action::NudgeRight => "nudge_right",
+            Action::NudgeUp => "nudge_up",
+            Action::NudgeDown => "nudge_down",
+            Action::FocusNext => "focus_next",
+            Action::FocusPrevious => "focus_previous",
+            Action::StartLink => "start_link",
+        }
+    }
+}
+
+/// Which keyboard shortcuts trigger which actions
+/// An action can have any number of shortcuts
+#[derive(Clone, Debug)]
+pub struct ShortcutMap {
+    shortcuts: Vec<(Action, KeyboardShortcut)>,
+}
+
+impl Default for ShortcutMap {
+    fn default() -> Self {
+        let shortcut = KeyboardShortcut::new;
+        Self {
+            shortcuts: vec![
+                (Action::Delete, shortcut(Modifiers::NONE, Key::Delete)),
+                (Action::Delete, shortcut(Modifiers::NONE, Key::Backspace)),
+                (Action::Duplicate, shortcut(Modifiers::COMMAND, Key::D)),
+                (Action::SelectAll, shortcut(Modifiers::COMMAND, Key::A)),
+                (Action::FrameAll, shortcut(Modifiers::NONE, Key::F)),
+                (Action::QuickAdd, shortcut(Modifiers::NONE, Key::Space)),
+                (Action::NudgeLeft, shortcut(Modifiers::NONE, Key::ArrowLeft)),
+                (Action::NudgeRight, shortcut(Modifiers::NONE, Key::ArrowRight)),
+                (Action::NudgeUp, shortcut(Modifiers::NONE, Key::ArrowUp)),
+                (Action::NudgeDown, shortcut(Modifiers::NONE, Key::ArrowDown)),
+                (Action::FocusNext, shortcut(Modifiers::NONE, Key::Tab)),
+                (Action::FocusPrevious, shortcut(Modifiers::SHIFT, Key::Tab)),
+                (Action::StartLink, shortcut(Modifiers::NONE, Key::Enter)),
+            ],
+        }
+    }
+}
+
+impl ShortcutMap {
+    /// Reads a shortcut config made of lines like `duplicate = Ctrl+D`
+    /// Actions that appear in the config lose their default shortcuts, the others keep them
+    /// Empty lines and lines starting with `#` are ignored
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut map = Self::default();
+        let mut configured = Vec::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| format!("line {}: {message}: {line}", line_number + 1);
+            let (name, shortcut) = line.split_once('=').ok_or_else(|| error("expected `action = shortcut`"))?;
+            let action = Action::ALL
+                .into_iter()
+                .find(|action| action.name() == name.trim())
+                .ok_or_else(|| error("unknown action"))?;
+            let shortcut = parse_shortcut(shortcut).ok_or_else(|| error("invalid shortcut"))?;
+            if !configured.contains(&action) {
+                configured.push(action);
+                map.shortcuts.retain(|(existing, _)| *existing != action);
+            }
+            map.shortcuts.push((action, shortcut));
+        }
+        Ok(map)
+    }
+
+    /// Consumes the key press of any shortcut for `action`, returning whether one was pressed
+    pub fn consume(&self, ctx: &Context, action: Action) -> bool {
+        self.shortcuts
+            .iter()
+            .filter(|(existing, _)| *existing == action)
+            .any(|(_, shortcut)| ctx.input_mut(|i| i.consume_shortcut(shortcut)))
+    }
+
+    /// The first shortcut for `action` formatted for display, or an empty string if it has none
+    pub fn shortcut_text(&self, ctx: &Context, action: Action) -> String {
+        self.shortcuts
+            .iter()
+            .find(|(existing, _)| *existing == action)
+            .map_or_else(String::new, |(_, shortcut)| ctx.format_shortcut(shortcut))
+    }
+}
+
+/// Parses a shortcut like `Ctrl+Shift+D`, with the key named as in `Key::from_name`
+/// `Ctrl` and `Cmd` both mean the platform's command key
+fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+    let mut parts: Vec<_> = text.split('+').map(str::trim).collect();
+    let key = Key::from_name(parts.pop()?)?;
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        modifiers = modifiers
+            | match part.to_lowercase().as_str() {
+                "ctrl" | "cmd" | "command" => Modifiers::COMMAND,
+                "alt" | "option" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                _ => return None,
+            };
+    }
+    Some(KeyboardShortcut::new(modifiers, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shortcuts_of(map: &ShortcutMap, action: Action) -> Vec<KeyboardShortcut> {
+        map.shortcuts.iter().filter(|(existing, _)| *existing == action).map(|(_, shortcut)| *shortcut).collect()
+    }
+
+    #[test]
+    fn parses_modifiers_and_keys() {
+        assert_eq!(parse_shortcut("D"), Some(KeyboardShortcut::new(Modifiers::NONE, Key::D)));
+        assert_eq!(parse_shortcut(" ctrl + Shift+D "), Some(KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::D)));
+        assert_eq!(parse_shortcut("Cmd+Alt+Delete"), parse_shortcut("Command+Option+Delete"));
+        assert_eq!(parse_shortcut("Alt+Delete"), Some(KeyboardShortcut::new(Modifiers::ALT, Key::Delete)));
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_modifiers() {
+        assert_eq!(parse_shortcut(""), None);
+        assert_eq!(parse_shortcut("Ctrl+"), None);
+        assert_eq!(parse_shortcut("Ctrl+Nothing"), None);
+        assert_eq!(parse_shortcut("Super+D"), None);
+        assert_eq!(parse_shortcut("Shift+Ctrl"), None);
+    }
+
+    #[test]
+    fn configured_actions_replace_their_defaults() {
+        let map = ShortcutMap::parse("# Comments and empty lines are skipped\n\nduplicate = Ctrl+Shift+D\nduplicate = Alt+D\n").unwrap();
+        assert_eq!(
+            shortcuts_of(&map, Action::Duplicate),
+            vec![KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::D), KeyboardShortcut::new(Modifiers::ALT, Key::D)],
+        );
+        assert_eq!(shortcuts_of(&map, Action::Delete), shortcuts_of(&ShortcutMap::default(), Action::Delete));
+    }
+
+    #[test]
+    fn errors_name_the_line_and_the_problem() {
+        let error = |text: &str| ShortcutMap::parse(text).unwrap_err();
+        assert_eq!(error("# Keys\n\nduplicate Ctrl+D"), "line 3: expected `action = shortcut`: duplicate Ctrl+D");
+        assert_eq!(error("teleport = T"), "line 1: unknown action: teleport = T");
+        assert_eq!(error("delete = Delete\nduplicate = Hyper+D"), "line 2: invalid shortcut: duplicate = Hyper+D");
+        assert_eq!(error("frame_all ="), "line 1: invalid shortcut: frame_all =");
+    }
+}
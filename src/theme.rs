@@ -0,0 +1,111 @@
+use eframe::egui::epaint::PathShape;
+use eframe::egui::Color32;
+use eframe::egui::Painter;
+use eframe::egui::Pos2;
+use eframe::egui::Rect;
+use eframe::egui::Stroke;
+use eframe::egui::Vec2;
+use eframe::egui::Visuals;
+
+/// The shape ports are drawn as
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortShape {
+    Circle,
+    Square,
+    Diamond,
+}
+
+impl PortShape {
+    /// Paints a port of this shape, about 10 points across
+    pub fn paint(self, painter: &Painter, center: Pos2, color: Color32) {
+        match self {
+            PortShape::Circle => {
+                painter.circle_filled(center, 5.0, color);
+            }
+            PortShape::Square => {
+                painter.rect_filled(Rect::from_center_size(center, Vec2::splat(9.0)), 1.0, color);
+            }
+            PortShape::Diamond => {
+                let points = vec![
+                    center + Vec2::new(0.0, -5.5),
+                    center + Vec2::new(5.5, 0.0),
+                    center + Vec2::new(0.0, 5.5),
+                    center + Vec2::new(-5.5, 0.0),
+                ];
+                painter.add(PathShape::convex_polygon(points, color, Stroke::NONE));
+            }
+        }
+    }
+}
+
+/// How a type of node wants to look, returned by `Node::style`
+/// Anything left as `None` comes from the graph's `Theme`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeStyle {
+    pub header_color: Option<Color32>,
+    /// Shown before the title, usually a single emoji
+    pub icon: Option<String>,
+    pub port_shape: Option<PortShape>,
+}
+
+/// The look of a whole graph, shared by all of its nodes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// The egui visuals used inside nodes, which also give the node background and border
+    pub visuals: Visuals,
+    pub canvas_fill: Color32,
+    pub header_color: Color32,
+    pub input_color: Color32,
+    pub output_color: Color32,
+    pub port_shape: PortShape,
+    pub link_color: Color32,
+    /// The color of links carrying fluids
+    pub pipe_color: Color32,
+    /// The space between the border of a node and its contents
+    pub inner_margin: f32,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            visuals: Visuals::dark(),
+            canvas_fill: Color32::from_gray(20),
+            header_color: Color32::from_gray(50),
+            input_color: Color32::from_rgb(80, 130, 255),
+            output_color: Color32::from_rgb(235, 90, 80),
+            port_shape: PortShape::Circle,
+            link_color: Color32::YELLOW,
+            pipe_color: Color32::from_rgb(80, 170, 230),
+            inner_margin: 8.0,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            visuals: Visuals::light(),
+            canvas_fill: Color32::from_gray(235),
+            header_color: Color32::from_gray(215),
+            input_color: Color32::from_rgb(40, 90, 210),
+            output_color: Color32::from_rgb(200, 55, 45),
+            port_shape: PortShape::Circle,
+            link_color: Color32::from_rgb(210, 130, 0),
+            pipe_color: Color32::from_rgb(30, 110, 200),
+            inner_margin: 8.0,
+        }
+    }
+
+    /// The header color for a node with `style`, toned down to suit the theme
+    pub fn header_color(&self, style: &NodeStyle) -> Color32 {
+        match style.header_color {
+            Some(color) if self.visuals.dark_mode => color.linear_multiply(0.6),
+            Some(color) => color.linear_multiply(0.45),
+            None => self.header_color,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}